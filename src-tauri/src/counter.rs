@@ -17,12 +17,12 @@ impl Counter {
 
     pub fn increment(&mut self) {
         self.value += 1;
-        self.emitter.emit_number("counter_updated", self.value);
+        self.emitter.emit_number("counter_updated", self.value.into());
     }
 
     pub fn reset(&mut self) {
         self.value = 0;
-        self.emitter.emit_number("counter_updated", self.value);
+        self.emitter.emit_number("counter_updated", self.value.into());
     }
 
     pub fn get_count_rate(&self) -> f64 {
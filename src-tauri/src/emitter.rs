@@ -1,69 +1,76 @@
+use std::sync::{mpsc, Arc, Mutex};
+
 use tauri::{AppHandle, Manager};
 
-use crate::tetromino::Tetromino;
-use crate::game::{BOARD_ROWS, BOARD_COLS};
+use tetris_core::event_sink::EventSink;
+use tetris_core::events::{GameEvent, VersionedEvent};
+
+/// All game events are sent to the interface on this channel.
+pub const GAME_EVENT_CHANNEL: &str = "game_event";
 
 #[derive(Clone, serde::Serialize)]
 struct NumberPayload {
   value: f64,
 }
 
-#[derive(Clone, serde::Serialize)]
-struct StringPayload {
-  value: String,
-}
-
-#[derive(Clone, serde::Serialize)]
-struct TetrominoPayload<'a> {
-    name: char,
-    occupied_positions: &'a Vec<(i32, i32)>
-}
-
-#[derive(Clone, serde::Serialize)]
-struct BoardPayload<'a> {
-    board: &'a [[char; BOARD_COLS]; BOARD_ROWS]
-}
+type EventSender = Arc<Mutex<Option<mpsc::Sender<VersionedEvent>>>>;
 
+/// Sends the events to the interface of the Tauri app, to the remote player when hosting a
+/// network game, and to the spectator server when it is running.
+#[derive(Clone)]
 pub struct Emitter {
-    app_handle: AppHandle
+    app_handle: AppHandle,
+    player: usize, // The player whose game sends the events
+    remote: EventSender, // Shared by the emitters of all players
+    spectators: Arc<Mutex<Option<mpsc::SyncSender<VersionedEvent>>>>, // Shared like remote
 }
 
 impl Emitter {
     pub fn new(app_handle: AppHandle) -> Self {
-        Emitter{ app_handle }
+        Emitter{
+            app_handle,
+            player: 0,
+            remote: Arc::new(Mutex::new(None)),
+            spectators: Arc::new(Mutex::new(None)),
+        }
     }
 
-    pub fn emit_string(&self, event_name: &str, payload: String) {
-        self.app_handle
-            .emit_all(event_name, StringPayload { value: payload })
-            .unwrap();
+    /// An emitter for the events of the game of the given player.
+    pub fn for_player(&self, player: usize) -> Self {
+        Emitter { player, ..self.clone() }
     }
 
-    pub fn emit_number<N>(&self, event_name: &str, payload: N)
-    where N: Into<f64> + Copy,
-    {
-        let payload = payload.into();
-        self.app_handle
-            .emit_all(event_name, NumberPayload { value: payload })
-            .unwrap();
+    /// Also send the events of every player to the given channel, or stop doing so.
+    pub fn set_remote(&self, remote: Option<mpsc::Sender<VersionedEvent>>) {
+        *self.remote.lock().unwrap() = remote;
+    }
+
+    /// Also send the events of every player to the spectators, or stop doing so. Events are
+    /// dropped for the spectators while the channel is full.
+    pub fn set_spectators(&self, spectators: Option<mpsc::SyncSender<VersionedEvent>>) {
+        *self.spectators.lock().unwrap() = spectators;
     }
 
-    pub fn emit_tetromino(&self, event_name: &str, tetromino: &Tetromino) {
-        let payload = TetrominoPayload{
-            name: tetromino.get_shape_name(),
-            occupied_positions: tetromino.get_occupied_positions()
-        };
+    /// Send a number on its own channel, for the parts of the app which are not part of the game.
+    pub fn emit_number(&self, event_name: &str, value: f64) {
         self.app_handle
-            .emit_all(event_name, payload)
+            .emit_all(event_name, NumberPayload { value })
             .unwrap();
     }
+}
 
-    pub fn emit_board(&self, event_name: &str, board: &[[char; BOARD_COLS]; BOARD_ROWS]) {
-        let payload = BoardPayload{
-            board
-        };
+impl EventSink for Emitter {
+    fn emit(&self, event: GameEvent) {
+        let event = VersionedEvent::new(event, self.player);
+        // The connection may already be closed, then the event is only for the interface
+        if let Some(sender) = self.remote.lock().unwrap().as_ref() {
+            let _ = sender.send(event.clone());
+        }
+        if let Some(sender) = self.spectators.lock().unwrap().as_ref() {
+            let _ = sender.try_send(event.clone());
+        }
         self.app_handle
-            .emit_all(event_name, payload)
+            .emit_all(GAME_EVENT_CHANNEL, event)
             .unwrap();
     }
 }
@@ -1,15 +1,32 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use tauri::{State, Manager};
+use std::collections::HashMap;
+use std::path::Path;
+
+use tauri::{State, Manager, WindowEvent};
 
 mod counter;
 mod emitter;
-mod tetromino;
-mod game;
+mod network;
+mod runner;
+mod settings;
+mod spectator;
+mod versus;
 use counter::{Counter, CounterRunner};
-use game::{Game, GameRunner, BOARD_ROWS, BOARD_COLS};
 use emitter::Emitter;
+use runner::GameRunner;
+use settings::Settings;
+
+use tetris_core::achievements::AchievementStatus;
+use tetris_core::command::CommandResult;
+use tetris_core::game::{Game, BOARD_ROWS, BOARD_COLS};
+use tetris_core::high_scores::HighScore;
+use tetris_core::lifetime::LifetimeStats;
+use tetris_core::piece_set::PieceSet;
+use tetris_core::puzzle::Puzzle;
+use tetris_core::replay::Replay;
+use tetris_core::stats::Stats;
 
 // Learn more about Tauri commands at https://tauri.app/v1/guides/features/command
 #[tauri::command]
@@ -58,22 +75,209 @@ fn reset_game(game_runner: State<GameRunner>) {
 }
 
 #[tauri::command]
-fn process_command(command: &str, game_runner: State<GameRunner>) -> bool {
-    // Early return if game is not running
-    if !game_runner.get_running() { return false; }
+fn process_command(
+    command: &str,
+    player: Option<usize>,
+    game_runner: State<GameRunner>) -> CommandResult {
+    // Commands are rejected if the game is not running, or the player is watching a replay.
+    // Commands without a player are for the first player.
+    game_runner.process_command(command, player.unwrap_or(0))
+}
+
+#[tauri::command]
+fn start_versus(shared_seed: bool, game_runner: State<GameRunner>) {
+    // Two players on the same keyboard, the match starts when the game is started
+    game_runner.start_versus(shared_seed);
+}
+
+#[tauri::command]
+fn start_cpu_versus(
+    difficulty: &str,
+    pieces_per_second: Option<f64>,
+    noise: Option<f64>,
+    game_runner: State<GameRunner>) -> Result<(), String> {
+    // The second game is played by a bot, the match starts when the game is started. Without
+    // pieces per second or noise, those of the difficulty are used.
+    game_runner.start_cpu_versus(difficulty, pieces_per_second, noise)
+}
+
+#[tauri::command]
+fn stop_versus(game_runner: State<GameRunner>) {
+    // Also leaves a network game
+    game_runner.stop_versus();
+}
+
+#[tauri::command]
+fn host_network_game(port: u16, game_runner: State<GameRunner>) -> Result<(), String> {
+    game_runner.host_network_game(port)
+}
+
+#[tauri::command]
+fn join_network_game(address: String, game_runner: State<GameRunner>) {
+    game_runner.join_network_game(address);
+}
+
+#[tauri::command]
+fn toggle_autoplay(game_runner: State<GameRunner>) -> bool {
+    // The bot plays the game when it is running, returns true if autoplay is on
+    game_runner.toggle_autoplay()
+}
+
+#[tauri::command]
+async fn start_external_bot(
+    program: String,
+    args: Vec<String>,
+    game_runner: State<'_, GameRunner>) -> Result<String, String> {
+    // The bot speaks the Tetris Bot Protocol, it stops like the built-in bot with toggle_autoplay.
+    // Starting the bot waits for its handshake, so it runs on a blocking thread and the window
+    // does not freeze meanwhile.
+    let game_runner = game_runner.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || game_runner.start_external_bot(&program, &args))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+#[tauri::command]
+fn load_puzzle(path: &str, game_runner: State<GameRunner>) -> Result<(), String> {
+    // Replace the current game by the puzzle in the given file
+    let puzzle = Puzzle::load(path)?;
+    game_runner.set_puzzle(Some(puzzle))
+}
+
+#[tauri::command]
+fn clear_puzzle(game_runner: State<GameRunner>) -> Result<(), String> {
+    // Go back to the normal game
+    game_runner.set_puzzle(None)
+}
+
+#[tauri::command]
+fn load_piece_set(path: &str, game_runner: State<GameRunner>) -> Result<(), String> {
+    // Replace the pieces of the game by the piece set in the given file
+    let piece_set = PieceSet::load(path)?;
+    game_runner.set_piece_set(piece_set);
+    Ok(())
+}
+
+#[tauri::command]
+fn select_piece_set(name: &str, game_runner: State<GameRunner>) -> Result<(), String> {
+    // Play with one of the built-in piece sets
+    let piece_set = PieceSet::builtin(name).ok_or(format!("Unknown piece set {}", name))?;
+    game_runner.set_piece_set(piece_set);
+    Ok(())
+}
+
+#[tauri::command]
+fn get_preview_size(game_runner: State<GameRunner>) -> usize {
+    // Size of the grid needed to show any piece of the current piece set
+    let game = game_runner.game.lock().unwrap();
+    game.get_piece_set().get_max_size()
+}
+
+#[tauri::command]
+fn get_piece_colors(game_runner: State<GameRunner>) -> HashMap<char, String> {
+    let game = game_runner.game.lock().unwrap();
+    game.get_piece_set().pieces.iter()
+        .map(|piece| (piece.name, piece.color.clone()))
+        .collect()
+}
+
+#[tauri::command]
+fn load_replay(path: &str, game_runner: State<GameRunner>) -> Result<u64, String> {
+    // Replace the current game by the replay in the given file, which starts paused.
+    // Returns the length of the replay in ticks.
+    let replay = Replay::load(Path::new(path))?;
+    let ticks = replay.ticks;
+    game_runner.load_replay(replay);
+    Ok(ticks)
+}
+
+#[tauri::command]
+fn play_replay(game_runner: State<GameRunner>) {
+    game_runner.run();
+}
+
+#[tauri::command]
+fn pause_replay(game_runner: State<GameRunner>) {
+    game_runner.pause();
+}
+
+#[tauri::command]
+fn set_replay_speed(speed: f64, game_runner: State<GameRunner>) -> Result<(), String> {
+    game_runner.set_replay_speed(speed)
+}
+
+#[tauri::command]
+fn seek_replay(tick: u64, game_runner: State<GameRunner>) -> Result<(), String> {
+    game_runner.seek_replay(tick)
+}
+
+#[tauri::command]
+fn step_replay_piece(game_runner: State<GameRunner>) -> Result<(), String> {
+    game_runner.step_replay_piece()
+}
+
+#[tauri::command]
+fn has_saved_game(game_runner: State<GameRunner>) -> bool {
+    game_runner.has_saved_game()
+}
+
+#[tauri::command]
+fn save_game(game_runner: State<GameRunner>) -> Result<(), String> {
+    game_runner.save_game()
+}
 
-    let mut game = game_runner.game.lock().unwrap();
-    // Early return if game is over
-    if game.get_game_over() {
-        return false;
-    }
+#[tauri::command]
+fn load_game(game_runner: State<GameRunner>) -> Result<(), String> {
+    // Resume the saved game, which starts paused
+    game_runner.load_game()
+}
 
-    match command {
-        "down" | "left" | "right" => { game.proces_arrow_key(command) },
-        "clockwise" | "counter-clockwise" => { game.process_rotation(command) },
-        "hard-drop" => { game.process_hard_drop(); true }
-        _ => { false }
-    }
+#[tauri::command]
+fn get_high_scores(
+    mode: Option<String>,
+    game_runner: State<GameRunner>) -> Result<Vec<HighScore>, String> {
+    // Leaderboard of the given mode, or of the mode currently played
+    game_runner.get_high_scores(mode)
+}
+
+#[tauri::command]
+fn submit_score(name: &str, game_runner: State<GameRunner>) -> Result<Option<usize>, String> {
+    // Submit the score of the finished game, returns the position on the leaderboard
+    game_runner.submit_score(name)
+}
+
+#[tauri::command]
+fn get_stats(game_runner: State<GameRunner>) -> Stats {
+    // Statistics of the current game
+    game_runner.game.lock().unwrap().get_stats()
+}
+
+#[tauri::command]
+fn get_lifetime_stats(game_runner: State<GameRunner>) -> Result<LifetimeStats, String> {
+    // Totals over all games played
+    game_runner.get_lifetime_stats()
+}
+
+#[tauri::command]
+fn reset_lifetime_stats(game_runner: State<GameRunner>) -> Result<(), String> {
+    game_runner.reset_lifetime_stats()
+}
+
+#[tauri::command]
+fn get_achievements(game_runner: State<GameRunner>) -> Vec<AchievementStatus> {
+    // All achievements, with the time they were unlocked
+    game_runner.get_achievements()
+}
+
+#[tauri::command]
+fn get_settings(game_runner: State<GameRunner>) -> Settings {
+    game_runner.get_settings()
+}
+
+#[tauri::command]
+fn update_settings(settings: Settings, game_runner: State<GameRunner>) -> Result<(), String> {
+    // Invalid settings are rejected and not stored
+    game_runner.update_settings(settings)
 }
 
 fn main() {
@@ -86,13 +290,22 @@ fn main() {
             let counter_runner = CounterRunner::new(Counter::new(1.0, emitter));
             app.manage(counter_runner);
 
-            let app_handle = app.handle();
-            let emitter = Emitter::new(app_handle);
-            let game_runner = GameRunner::new(Game::new(emitter));
+            let emitter = Emitter::new(app.handle());
+            let data_dir = app.path_resolver().app_data_dir();
+            let game_runner = GameRunner::new(Game::new(Box::new(emitter.clone())), emitter, data_dir);
             app.manage(game_runner);
 
             Ok(())
         })
+        .on_window_event(|event| {
+            // Save the game in progress when the app is closed, so it can be resumed later
+            if let WindowEvent::CloseRequested { .. } = event.event() {
+                let game_runner = event.window().state::<GameRunner>();
+                if let Err(err) = game_runner.save_game() {
+                    println!("Game not saved: {}", err);
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             start_counter,
@@ -103,6 +316,36 @@ fn main() {
             start_game,
             reset_game,
             process_command,
+            toggle_autoplay,
+            start_external_bot,
+            start_versus,
+            start_cpu_versus,
+            stop_versus,
+            host_network_game,
+            join_network_game,
+            load_puzzle,
+            clear_puzzle,
+            load_piece_set,
+            select_piece_set,
+            get_preview_size,
+            get_piece_colors,
+            load_replay,
+            play_replay,
+            pause_replay,
+            set_replay_speed,
+            seek_replay,
+            step_replay_piece,
+            has_saved_game,
+            save_game,
+            load_game,
+            get_high_scores,
+            submit_score,
+            get_stats,
+            get_lifetime_stats,
+            reset_lifetime_stats,
+            get_achievements,
+            get_settings,
+            update_settings,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
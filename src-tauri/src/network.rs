@@ -0,0 +1,249 @@
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tungstenite::{Message, WebSocket};
+
+use tetris_core::command::Command;
+use tetris_core::event_sink::EventSink;
+use tetris_core::events::{GameEvent, VersionedEvent};
+
+use crate::emitter::Emitter;
+use crate::runner::GameRunner;
+
+/// Version of the messages between the host and the client, both have to use the same version.
+pub const NETWORK_VERSION: u32 = 1;
+/// Channel of the interface on which 1 is sent when the other player is connected, and 0 when
+/// the connection is lost.
+pub const CONNECTED_CHANNEL: &str = "network_connected";
+
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+// The other player is dropped when it stops reading, so it can not block the game
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Messages between the host and the client, sent as JSON text over the WebSocket. The kind of
+/// message is in the "message" field, because events already have a "type" field.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "message", rename_all = "snake_case")]
+pub enum NetMessage {
+    /// The client joined the game, or joined again after the connection was lost.
+    Join { version: u32 },
+    /// A command of the client for its own game.
+    Input { command: Command },
+    /// An event of one of the games, with the players as seen by the receiver. The host sends
+    /// the boards, pieces and pending garbage of both games this way.
+    Event(VersionedEvent),
+}
+
+/// A versus match between two machines. The host runs the games of both players, with the
+/// garbage exchanged between them, while the client sends the inputs of its player to the host
+/// and shows the events of both games which it gets back. The connection is closed on drop.
+pub struct Connection {
+    stop: Arc<AtomicBool>,
+    inputs: Option<mpsc::Sender<Command>>, // Only the client sends its inputs
+}
+
+impl Connection {
+    /// Wait for a client on the given port. The client plays the second game of the versus
+    /// match, and it can connect again when the connection is lost.
+    pub fn host(
+        port: u16,
+        runner: GameRunner,
+        events: mpsc::Receiver<VersionedEvent>,
+        emitter: Emitter) -> Result<Self, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .map_err(|err| format!("Could not listen on port {}: {}", port, err))?;
+        listener.set_nonblocking(true).map_err(|err| err.to_string())?;
+        println!("Hosting a network game on port {}", port);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+        thread::spawn(move || {
+            let mut client: Option<WebSocket<TcpStream>> = None;
+            while !stop_clone.load(Ordering::SeqCst) {
+                // A new client replaces the old one, which may not have noticed yet that its
+                // connection was lost
+                if let Ok((stream, address)) = listener.accept() {
+                    match accept_client(stream) {
+                        Ok(socket) => {
+                            println!("Player 2 joined from {}", address);
+                            client = Some(socket);
+                            emitter.emit_number(CONNECTED_CHANNEL, 1.);
+                            // Send the complete state of both games to the new client
+                            while events.try_recv().is_ok() {}
+                            runner.emit_all_games();
+                        }
+                        Err(err) => println!("Could not accept player 2: {}", err),
+                    }
+                }
+
+                // Events are dropped while there is no client, it gets the complete state when
+                // it joins
+                let mut connected = true;
+                while let Ok(event) = events.try_recv() {
+                    if let (Some(socket), Some(event)) = (client.as_mut(), event_for_client(event)) {
+                        connected &= send(socket, &NetMessage::Event(event)).is_ok();
+                    }
+                }
+                if let Some(socket) = client.as_mut() {
+                    while connected {
+                        match receive(socket) {
+                            Ok(Some(NetMessage::Input { command })) => {
+                                runner.apply_remote_command(command);
+                            }
+                            Ok(Some(_)) => {}
+                            Ok(None) => break,
+                            Err(_) => connected = false,
+                        }
+                    }
+                }
+                if !connected {
+                    println!("The connection with player 2 was lost");
+                    client = None;
+                    runner.pause();
+                    emitter.emit_number(CONNECTED_CHANNEL, 0.);
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+        Ok(Connection { stop, inputs: None })
+    }
+
+    /// Join the game of the host at the given address, e.g. "192.168.1.10:9001". The client
+    /// keeps trying to connect until the connection is closed.
+    pub fn join(address: String, emitter: Emitter) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+        let (inputs, input_receiver) = mpsc::channel();
+        thread::spawn(move || {
+            while !stop_clone.load(Ordering::SeqCst) {
+                let mut socket = match connect(&address) {
+                    Ok(socket) => socket,
+                    Err(err) => {
+                        println!("Could not connect to {}: {}", address, err);
+                        thread::sleep(RECONNECT_INTERVAL);
+                        continue;
+                    }
+                };
+                println!("Joined the game of {}", address);
+                emitter.emit_number(CONNECTED_CHANNEL, 1.);
+                // Inputs given while the connection was lost are not sent anymore
+                while input_receiver.try_recv().is_ok() {}
+
+                let mut connected = true;
+                while connected && !stop_clone.load(Ordering::SeqCst) {
+                    while let Ok(command) = input_receiver.try_recv() {
+                        connected &= send(&mut socket, &NetMessage::Input { command }).is_ok();
+                    }
+                    loop {
+                        match receive(&mut socket) {
+                            Ok(Some(NetMessage::Event(event))) => {
+                                emitter.for_player(event.player).emit(event.event);
+                            }
+                            Ok(Some(_)) => {}
+                            Ok(None) => break,
+                            Err(_) => {
+                                connected = false;
+                                break;
+                            }
+                        }
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+                if !connected {
+                    println!("The connection with {} was lost, reconnecting", address);
+                    emitter.emit_number(CONNECTED_CHANNEL, 0.);
+                }
+            }
+        });
+        Connection { stop, inputs: Some(inputs) }
+    }
+
+    pub fn is_client(&self) -> bool {
+        self.inputs.is_some()
+    }
+
+    /// Send a command of the client to the host. Returns false if this is not a client.
+    pub fn send_input(&self, command: Command) -> bool {
+        self.inputs.as_ref().is_some_and(|inputs| inputs.send(command).is_ok())
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+fn accept_client(stream: TcpStream) -> Result<WebSocket<TcpStream>, String> {
+    stream.set_nonblocking(false).map_err(|err| err.to_string())?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).map_err(|err| err.to_string())?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT)).map_err(|err| err.to_string())?;
+    let mut socket = tungstenite::accept(stream).map_err(|err| err.to_string())?;
+    match receive(&mut socket)? {
+        Some(NetMessage::Join { version }) if version == NETWORK_VERSION => {}
+        Some(NetMessage::Join { version }) => {
+            return Err(format!("Unsupported network version {}", version));
+        }
+        _ => return Err("The client did not join".to_string()),
+    }
+    socket.get_mut().set_read_timeout(Some(POLL_INTERVAL)).map_err(|err| err.to_string())?;
+    Ok(socket)
+}
+
+fn connect(address: &str) -> Result<WebSocket<TcpStream>, String> {
+    let stream = TcpStream::connect(address).map_err(|err| err.to_string())?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).map_err(|err| err.to_string())?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT)).map_err(|err| err.to_string())?;
+    let (mut socket, _) = tungstenite::client(format!("ws://{}", address), stream)
+        .map_err(|err| err.to_string())?;
+    send(&mut socket, &NetMessage::Join { version: NETWORK_VERSION })?;
+    socket.get_mut().set_read_timeout(Some(POLL_INTERVAL)).map_err(|err| err.to_string())?;
+    Ok(socket)
+}
+
+/// Fails when the message could not be sent before the write timeout, then the connection is
+/// treated as lost.
+fn send(socket: &mut WebSocket<TcpStream>, message: &NetMessage) -> Result<(), String> {
+    let text = serde_json::to_string(message).map_err(|err| err.to_string())?;
+    socket.send(Message::Text(text)).map_err(|err| err.to_string())
+}
+
+/// The next message, or None if no message arrived before the read timeout. Messages which can
+/// not be parsed are skipped.
+fn receive(socket: &mut WebSocket<TcpStream>) -> Result<Option<NetMessage>, String> {
+    match socket.read() {
+        Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+            Ok(message) => Ok(Some(message)),
+            Err(err) => {
+                println!("Invalid network message: {}", err);
+                receive(socket)
+            }
+        },
+        Ok(Message::Close(_)) => Err("Connection closed".to_string()),
+        Ok(_) => receive(socket),
+        Err(tungstenite::Error::Io(err))
+            if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// The event as seen by the client, whose game is the second game of the host. Events about
+/// the app of the host, like its achievements, are not sent.
+fn event_for_client(event: VersionedEvent) -> Option<VersionedEvent> {
+    let player = 1 - event.player.min(1);
+    let event = match event.event {
+        GameEvent::AchievementUnlocked(_) | GameEvent::NewRecord { .. } | GameEvent::LifetimeStats(_) => {
+            return None;
+        }
+        GameEvent::VersusOver { winner } => GameEvent::VersusOver { winner: winner.map(|winner| 1 - winner) },
+        event => event,
+    };
+    Some(VersionedEvent::new(event, player))
+}
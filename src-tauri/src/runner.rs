@@ -0,0 +1,716 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex, atomic};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tetris_core::achievements::{AchievementStatus, AchievementTracker, EngineEvent};
+use tetris_core::bot::{Autoplayer, Bot, Weights};
+use tetris_core::command::{Command, CommandResult};
+use tetris_core::event_sink::EventSink;
+use tetris_core::events::GameEvent;
+use tetris_core::game::{Game, SavedGame, TICK_RATE};
+use tetris_core::garbage::GarbageSettings;
+use tetris_core::high_scores::{HighScore, HighScoreTable};
+use tetris_core::lifetime::LifetimeStats;
+use tetris_core::piece_set::PieceSet;
+use tetris_core::puzzle::Puzzle;
+use tetris_core::replay::{GameRules, Replay, ReplayPlayer};
+use tetris_core::tbp::TbpBot;
+
+use crate::emitter::Emitter;
+use crate::network::Connection;
+use crate::settings::Settings;
+use crate::spectator::{SpectatorServer, EVENT_BUFFER};
+use crate::versus::{CpuSettings, Difficulty, Versus};
+
+const SAVE_FILE_NAME: &str = "savegame.json";
+const HIGH_SCORES_FILE_NAME: &str = "high_scores.json";
+const SETTINGS_FILE_NAME: &str = "settings.json";
+const LIFETIME_STATS_FILE_NAME: &str = "lifetime_stats.json";
+const ACHIEVEMENTS_DIR_NAME: &str = "achievements"; // Directory with custom achievements
+const UNLOCKED_ACHIEVEMENTS_FILE_NAME: &str = "achievements_unlocked.json";
+
+fn load_achievements(data_dir: Option<&Path>) -> AchievementTracker {
+    // Custom achievements and unlocks are only available if there is a data directory
+    let custom_dir = data_dir.map(|data_dir| data_dir.join(ACHIEVEMENTS_DIR_NAME));
+    let mut achievements = AchievementTracker::new(custom_dir.as_deref()).unwrap_or_else(|err| {
+        println!("Could not load custom achievements: {}", err);
+        AchievementTracker::new(None).expect("The built-in achievements are valid")
+    });
+    if let Some(data_dir) = data_dir {
+        let path = data_dir.join(UNLOCKED_ACHIEVEMENTS_FILE_NAME);
+        if let Err(err) = achievements.load_unlocked(&path) {
+            println!("Could not load unlocked achievements: {}", err);
+        }
+    }
+    achievements
+}
+
+fn load_settings(data_dir: Option<&Path>) -> Settings {
+    let Some(data_dir) = data_dir else { return Settings::default(); };
+    Settings::load(&data_dir.join(SETTINGS_FILE_NAME)).unwrap_or_else(|err| {
+        println!("Could not load settings, using the defaults: {}", err);
+        Settings::default()
+    })
+}
+
+fn apply_settings(game: &mut Game, settings: &Settings) {
+    // The keybindings and the auto shift are handled by the interface
+    game.set_start_level(settings.start_level);
+    game.set_finesse_training(settings.finesse_training);
+    game.set_preview_count(settings.preview_count);
+    game.set_garbage_settings(GarbageSettings {
+        delay_ticks: (settings.garbage_delay as f64 * TICK_RATE / 1000.).round() as u64,
+        messiness: settings.garbage_messiness,
+    });
+}
+
+// Declare a shared game struct to use the state of the game
+// Arc Mutex makes it usable in different threads
+#[derive(Clone)]
+pub struct GameRunner {
+    pub game: Arc<Mutex<Game>>,
+    versus: Arc<Mutex<Option<Versus>>>, // Set while two players play against each other
+    network: Arc<Mutex<Option<Connection>>>, // Set while hosting or joining a network game
+    spectator: Arc<Mutex<Option<SpectatorServer>>>, // Set while the spectator server runs
+    running: Arc<atomic::AtomicBool>,
+    run_id: Arc<atomic::AtomicUsize>, // Identifies the thread which is allowed to run the game
+    tick_rate: f64,
+    data_dir: Option<PathBuf>, // Directory to store files in, nothing is stored if None
+    replay_player: Arc<Mutex<Option<ReplayPlayer>>>, // Set while playing back a replay
+    player_rules: Arc<Mutex<Option<GameRules>>>, // Rules of the player while a replay is played
+    autoplay: Arc<Mutex<Option<Box<dyn Autoplayer>>>>, // Set while a bot plays the game
+    assisted: Arc<atomic::AtomicBool>, // A bot played a part of the current game
+    achievements: Arc<Mutex<AchievementTracker>>,
+    settings: Arc<Mutex<Settings>>,
+    emitter: Emitter,
+}
+
+impl GameRunner {
+    pub fn new(mut game: Game, emitter: Emitter, data_dir: Option<PathBuf>) -> Self {
+        let achievements = load_achievements(data_dir.as_deref());
+        let settings = load_settings(data_dir.as_deref());
+        if let Some(piece_set) = PieceSet::builtin(&settings.default_mode) {
+            game.set_piece_set(piece_set);
+        }
+        apply_settings(&mut game, &settings);
+        let spectator_settings = settings.clone();
+        let runner = GameRunner{
+            game: Arc::new(Mutex::new(game)),
+            versus: Arc::new(Mutex::new(None)),
+            network: Arc::new(Mutex::new(None)),
+            spectator: Arc::new(Mutex::new(None)),
+            running: Arc::new(atomic::AtomicBool::new(false)),
+            run_id: Arc::new(atomic::AtomicUsize::new(0)),
+            tick_rate: TICK_RATE,
+            data_dir,
+            replay_player: Arc::new(Mutex::new(None)),
+            player_rules: Arc::new(Mutex::new(None)),
+            autoplay: Arc::new(Mutex::new(None)),
+            assisted: Arc::new(atomic::AtomicBool::new(false)),
+            achievements: Arc::new(Mutex::new(achievements)),
+            settings: Arc::new(Mutex::new(settings)),
+            emitter,
+        };
+        if let Err(err) = runner.update_spectator_server(&spectator_settings) {
+            println!("{}", err);
+        }
+        runner
+    }
+
+    pub fn get_running(&self) -> bool {
+        self.running.load(atomic::Ordering::SeqCst)
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay_player.lock().unwrap().is_some()
+    }
+
+    pub fn run(&self) {
+        // Early return if running is true: the game is already started
+        if self.running.load(atomic::Ordering::SeqCst) {
+            println!("Game is already running!");
+            return;
+        }
+        if self.is_network_client() {
+            println!("The game is started by the host");
+            return;
+        }
+
+        // Early return if the game-state is game-over. Game should be reset before running
+        // Otherwise emit the current game state to sync the interface
+        if self.game.lock().unwrap().get_game_over() {
+            return;
+        }
+        self.emit_all_games();
+
+        // Set running flag to true. A thread of an earlier run which has not noticed yet that
+        // it was stopped, stops because its run id is outdated.
+        self.running.store(true, atomic::Ordering::SeqCst);
+        let run_id = self.run_id.fetch_add(1, atomic::Ordering::SeqCst) + 1;
+
+        // Clone self to move it to the background thread
+        let self_clone = self.clone();
+
+        // Spawn a thread to increment the game at set intervals
+        thread::spawn(move || {
+            // Continue as long as running is true
+            while self_clone.running.load(atomic::Ordering::SeqCst)
+                && self_clone.run_id.load(atomic::Ordering::SeqCst) == run_id {
+                let speed;
+                {
+                    let mut game = self_clone.game.lock().unwrap();
+                    let mut versus = self_clone.versus.lock().unwrap();
+                    let mut replay_player = self_clone.replay_player.lock().unwrap();
+                    let mut success = match replay_player.as_mut() {
+                        Some(player) => player.tick(&mut game),
+                        None => {
+                            self_clone.autoplay_step(&mut game);
+                            game.tick()
+                        }
+                    };
+                    if let Some(versus) = versus.as_mut() {
+                        success = versus.tick(&mut game, &self_clone.emitter);
+                    }
+                    // Replays do not unlock achievements
+                    let events = game.drain_engine_events();
+                    if replay_player.is_none() {
+                        self_clone.handle_engine_events(events);
+                    }
+                    if !success {
+                        // Game over, stop running and end loop
+                        self_clone.running.store(false, atomic::Ordering::SeqCst);
+                        if replay_player.is_none() {
+                            // Versus games depend on the garbage of the opponent, so they can
+                            // not be replayed
+                            if versus.is_none() {
+                                self_clone.save_replay(&game);
+                            }
+                            self_clone.record_lifetime_stats(&mut game);
+                        }
+                        break
+                    }
+                    speed = replay_player.as_ref().map_or(1.0, |player| player.get_speed());
+                }
+                thread::sleep(Duration::from_secs_f64(1. / (self_clone.tick_rate * speed)));
+            }
+        });
+    }
+
+    /// Let the bot play the game, or stop it. Returns true if the bot is playing.
+    pub fn toggle_autoplay(&self) -> bool {
+        let mut autoplay = self.autoplay.lock().unwrap();
+        *autoplay = match autoplay.take() {
+            Some(_) => None,
+            None => Some(Box::new(Bot::new(Weights::default())))
+        };
+        autoplay.is_some()
+    }
+
+    /// Let an external bot which speaks the Tetris Bot Protocol play the game, started with
+    /// the given program and arguments. Returns the name of the bot. Waits until the bot is
+    /// ready, which may take a while.
+    pub fn start_external_bot(&self, program: &str, args: &[String]) -> Result<String, String> {
+        let bot = TbpBot::spawn(program, args)?;
+        let name = bot.get_name().to_string();
+        println!("External bot {} started", name);
+        *self.autoplay.lock().unwrap() = Some(Box::new(bot));
+        Ok(name)
+    }
+
+    fn autoplay_step(&self, game: &mut Game) {
+        // The bot applies one command per tick, like a player would
+        let mut autoplay = self.autoplay.lock().unwrap();
+        let Some(bot) = autoplay.as_mut() else { return; };
+        self.assisted.store(true, atomic::Ordering::SeqCst);
+        if let Some(command) = bot.next_command(game) {
+            if game.process_command(command) != CommandResult::Accepted {
+                bot.discard_plan();
+            }
+        }
+        if let Some(err) = bot.get_error() {
+            println!("The bot stopped playing: {}", err);
+            *autoplay = None;
+        }
+    }
+
+    pub fn pause(&self) {
+        self.running.store(false, atomic::Ordering::SeqCst);
+    }
+
+    /// Start a versus match of two players on the same keyboard, with the current piece set. If
+    /// the seed is shared, both players get the same sequence of pieces.
+    pub fn start_versus(&self, shared_seed: bool) {
+        self.pause();
+        self.end_game();
+        self.end_versus();
+        let mut game = self.game.lock().unwrap();
+        let mut versus = Versus::new(&game, &self.emitter, shared_seed);
+        apply_settings(&mut versus.opponent, &self.settings.lock().unwrap());
+        versus.restart(&mut game);
+        *self.versus.lock().unwrap() = Some(versus);
+    }
+
+    /// Start a versus match against a CPU opponent of the given difficulty, e.g. "medium". The
+    /// pieces per second and the noise of the difficulty can be replaced by other values.
+    pub fn start_cpu_versus(
+        &self,
+        difficulty: &str,
+        pieces_per_second: Option<f64>,
+        noise: Option<f64>) -> Result<(), String> {
+        let preset = Difficulty::parse(difficulty)
+            .ok_or(format!("Unknown difficulty {}", difficulty))?
+            .get_cpu_settings();
+        let cpu_settings = CpuSettings {
+            pieces_per_second: pieces_per_second.unwrap_or(preset.pieces_per_second),
+            noise: noise.unwrap_or(preset.noise),
+        };
+        cpu_settings.validate()?;
+        self.pause();
+        self.end_game();
+        self.end_versus();
+        let mut game = self.game.lock().unwrap();
+        let mut versus = Versus::against_cpu(&game, &self.emitter, cpu_settings);
+        apply_settings(&mut versus.opponent, &self.settings.lock().unwrap());
+        versus.restart(&mut game);
+        *self.versus.lock().unwrap() = Some(versus);
+        Ok(())
+    }
+
+    /// Go back to the game of a single player.
+    pub fn stop_versus(&self) {
+        self.pause();
+        self.end_game();
+        self.end_versus();
+        self.game.lock().unwrap().reset();
+    }
+
+    /// Also leaves the network game, because the network game is a versus match.
+    fn end_versus(&self) {
+        *self.versus.lock().unwrap() = None;
+        *self.network.lock().unwrap() = None;
+        self.emitter.set_remote(None);
+    }
+
+    /// Host a versus match in which the second player joins over the network, on the given
+    /// port. Both players get the same sequence of pieces.
+    pub fn host_network_game(&self, port: u16) -> Result<(), String> {
+        self.end_versus();
+        let (sender, receiver) = mpsc::channel();
+        let connection = Connection::host(port, self.clone(), receiver, self.emitter.clone())?;
+        self.start_versus(true);
+        self.emitter.set_remote(Some(sender));
+        *self.network.lock().unwrap() = Some(connection);
+        Ok(())
+    }
+
+    /// Join the network game hosted at the given address. The games are run by the host, this
+    /// app only sends the inputs of its player and shows the games.
+    pub fn join_network_game(&self, address: String) {
+        self.pause();
+        self.end_game();
+        self.end_versus();
+        *self.network.lock().unwrap() = Some(Connection::join(address, self.emitter.clone()));
+    }
+
+    fn is_network_client(&self) -> bool {
+        self.network.lock().unwrap().as_ref().is_some_and(|connection| connection.is_client())
+    }
+
+    /// Send the complete state of every game, e.g. to sync the interface.
+    pub fn emit_all_games(&self) {
+        let mut game = self.game.lock().unwrap();
+        game.emit_all();
+        if let Some(versus) = self.versus.lock().unwrap().as_mut() {
+            versus.opponent.emit_all();
+        }
+    }
+
+    pub fn is_versus(&self) -> bool {
+        self.versus.lock().unwrap().is_some()
+    }
+
+    /// Apply a command of the given player, given by its name, to the running game. The second
+    /// player only has a game in versus mode.
+    pub fn process_command(&self, command: &str, player: usize) -> CommandResult {
+        match Command::parse(command) {
+            Some(command) => self.apply_command(command, player),
+            None => CommandResult::UnknownCommand
+        }
+    }
+
+    /// Apply a command of the given player of this app to the running game. In a network game
+    /// the commands of the client are forwarded to the host, without knowing whether the host
+    /// accepts them, and the second game of the host is only played by the client.
+    pub fn apply_command(&self, command: Command, player: usize) -> CommandResult {
+        if let Some(connection) = self.network.lock().unwrap().as_ref() {
+            if connection.is_client() {
+                return match player == 0 && connection.send_input(command) {
+                    true => CommandResult::Forwarded,
+                    false => CommandResult::UnknownPlayer
+                };
+            }
+            if player > 0 {
+                return CommandResult::UnknownPlayer;
+            }
+        }
+        self.apply_player_command(command, player)
+    }
+
+    /// Apply a command of the client of the network game, which plays the second game.
+    pub fn apply_remote_command(&self, command: Command) -> CommandResult {
+        self.apply_player_command(command, 1)
+    }
+
+    fn apply_player_command(&self, command: Command, player: usize) -> CommandResult {
+        if self.is_replaying() {
+            return CommandResult::NotRunning;
+        }
+
+        let mut game = self.game.lock().unwrap();
+        if !self.get_running() {
+            return match (game.get_game_over(), game.get_tick_count()) {
+                (true, _) => CommandResult::GameOver,
+                (false, 0) => CommandResult::NotRunning,
+                (false, _) => CommandResult::Paused,
+            };
+        }
+        if player > 0 {
+            return match self.versus.lock().unwrap().as_mut() {
+                Some(versus) if player == 1 && !versus.is_against_cpu() => {
+                    versus.opponent.process_command(command)
+                }
+                _ => CommandResult::UnknownPlayer
+            };
+        }
+        let result = game.process_command(command);
+        self.handle_engine_events(game.drain_engine_events());
+        result
+    }
+
+    /// Games played by a bot do not unlock achievements.
+    fn handle_engine_events(&self, events: Vec<EngineEvent>) {
+        if events.is_empty() || self.is_assisted() {
+            return;
+        }
+        let time = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        let mut achievements = self.achievements.lock().unwrap();
+        let unlocked: Vec<AchievementStatus> = events.iter()
+            .flat_map(|event| achievements.handle(event, time))
+            .collect();
+        if unlocked.is_empty() {
+            return;
+        }
+
+        for achievement in &unlocked {
+            println!("Achievement unlocked: {}", achievement.achievement.name);
+            self.emitter.emit(GameEvent::AchievementUnlocked(achievement.clone()));
+        }
+        if let Some(data_dir) = &self.data_dir {
+            let path = data_dir.join(UNLOCKED_ACHIEVEMENTS_FILE_NAME);
+            if let Err(err) = achievements.save_unlocked(&path) {
+                println!("Could not save achievements: {}", err);
+            }
+        }
+    }
+
+    pub fn get_settings(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Validate and store the settings, and apply them to the game.
+    pub fn update_settings(&self, settings: Settings) -> Result<(), String> {
+        settings.validate()?;
+        self.update_spectator_server(&settings)?;
+        if let Some(data_dir) = &self.data_dir {
+            settings.save(&data_dir.join(SETTINGS_FILE_NAME))?;
+        }
+        // A replay keeps its own rules, like finesse training, the settings apply after it
+        if !self.is_replaying() {
+            apply_settings(&mut self.game.lock().unwrap(), &settings);
+        }
+        if let Some(versus) = self.versus.lock().unwrap().as_mut() {
+            apply_settings(&mut versus.opponent, &settings);
+        }
+        *self.settings.lock().unwrap() = settings;
+        Ok(())
+    }
+
+    /// Start or stop the spectator server as the settings say, it is restarted when the port
+    /// changes.
+    fn update_spectator_server(&self, settings: &Settings) -> Result<(), String> {
+        let mut spectator = self.spectator.lock().unwrap();
+        let port = settings.spectator_server.then_some(settings.spectator_port);
+        if spectator.as_ref().map(|server| server.get_port()) == port {
+            return Ok(());
+        }
+        *spectator = None;
+        self.emitter.set_spectators(None);
+        let Some(port) = port else { return Ok(()); };
+
+        let (sender, receiver) = mpsc::sync_channel(EVENT_BUFFER);
+        let runner = self.clone();
+        *spectator = Some(SpectatorServer::start(port, receiver, move || runner.emit_all_games())?);
+        self.emitter.set_spectators(Some(sender));
+        Ok(())
+    }
+
+    pub fn get_achievements(&self) -> Vec<AchievementStatus> {
+        self.achievements.lock().unwrap().get_status()
+    }
+
+    fn save_replay(&self, game: &Game) {
+        let Some(data_dir) = &self.data_dir else { return; };
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let path = data_dir.join("replays").join(format!("replay-{}.json", timestamp));
+
+        match game.get_replay().save(&path) {
+            Ok(_) => println!("Saved replay to {}", path.display()),
+            Err(err) => println!("Could not save replay: {}", err)
+        }
+    }
+
+    /// Stop the current game and prepare playing back the replay. The replay starts playing
+    /// when the game is run.
+    pub fn load_replay(&self, replay: Replay) {
+        self.pause();
+        self.end_game();
+        self.end_versus();
+        let mut game = self.game.lock().unwrap();
+        // The replay is played with its own rules, the next game uses the rules of the player
+        *self.player_rules.lock().unwrap() = Some(game.get_rules());
+        let mut player = ReplayPlayer::new(replay);
+        player.restart(&mut game);
+        *self.replay_player.lock().unwrap() = Some(player);
+    }
+
+    pub fn set_replay_speed(&self, speed: f64) -> Result<(), String> {
+        match self.replay_player.lock().unwrap().as_mut() {
+            Some(player) => player.set_speed(speed),
+            None => Err("No replay is loaded".to_string())
+        }
+    }
+
+    /// Pause the replay and move it to the given tick, by simulating the replay from the start.
+    pub fn seek_replay(&self, tick: u64) -> Result<(), String> {
+        self.pause();
+        let mut game = self.game.lock().unwrap();
+        let mut replay_player = self.replay_player.lock().unwrap();
+        let player = replay_player.as_mut().ok_or("No replay is loaded".to_string())?;
+
+        game.set_muted(true);
+        player.restart(&mut game);
+        while game.get_tick_count() < tick && player.tick(&mut game) {}
+        game.set_muted(false);
+        game.emit_all();
+        Ok(())
+    }
+
+    /// Pause the replay and forward it until the next piece is placed.
+    pub fn step_replay_piece(&self) -> Result<(), String> {
+        self.pause();
+        let mut game = self.game.lock().unwrap();
+        let mut replay_player = self.replay_player.lock().unwrap();
+        let player = replay_player.as_mut().ok_or("No replay is loaded".to_string())?;
+
+        let pieces_placed = game.get_pieces_placed();
+        game.set_muted(true);
+        while game.get_pieces_placed() == pieces_placed && player.tick(&mut game) {}
+        game.set_muted(false);
+        game.emit_all();
+        Ok(())
+    }
+
+    fn get_save_path(&self) -> Result<PathBuf, String> {
+        match &self.data_dir {
+            Some(data_dir) => Ok(data_dir.join(SAVE_FILE_NAME)),
+            None => Err("No directory available to store the game".to_string())
+        }
+    }
+
+    pub fn has_saved_game(&self) -> bool {
+        self.get_save_path().is_ok_and(|path| path.exists())
+    }
+
+    /// Pause the game and save it, so it can be resumed later. Replays, finished games, games
+    /// played by a bot and games which have not been started are not saved.
+    pub fn save_game(&self) -> Result<(), String> {
+        self.pause();
+        let path = self.get_save_path()?;
+        let game = self.game.lock().unwrap();
+        if self.is_versus() {
+            return Err("Versus games can not be saved".to_string());
+        }
+        // The saved game would lose that it was played by a bot
+        if self.is_assisted() {
+            return Err("Games played by a bot can not be saved".to_string());
+        }
+        if self.is_replaying() || game.get_game_over() || game.get_tick_count() == 0 {
+            return Err("There is no game in progress to save".to_string());
+        }
+        game.save_state().save(&path)?;
+        println!("Saved game to {}", path.display());
+        Ok(())
+    }
+
+    /// Replace the current game by the saved game, which is paused until the game is run. The
+    /// saved game is removed, so it can only be resumed once.
+    pub fn load_game(&self) -> Result<(), String> {
+        self.pause();
+        self.end_game();
+        self.end_versus();
+        let path = self.get_save_path()?;
+        let saved_game = SavedGame::load(&path)?;
+        let mut game = self.game.lock().unwrap();
+        game.restore_state(saved_game);
+        fs::remove_file(&path)
+            .map_err(|err| format!("Could not remove {}: {}", path.display(), err))
+    }
+
+    fn get_high_scores_path(&self) -> Result<PathBuf, String> {
+        match &self.data_dir {
+            Some(data_dir) => Ok(data_dir.join(HIGH_SCORES_FILE_NAME)),
+            None => Err("No directory available to store high scores".to_string())
+        }
+    }
+
+    /// The leaderboard of the given mode, or of the mode currently played if None.
+    pub fn get_high_scores(&self, mode: Option<String>) -> Result<Vec<HighScore>, String> {
+        let mode = mode.unwrap_or_else(|| self.game.lock().unwrap().get_mode());
+        let table = HighScoreTable::load(&self.get_high_scores_path()?)?;
+        Ok(table.get(&mode))
+    }
+
+    /// Submit the score of the finished game to the leaderboard of its mode. Returns the
+    /// position on the leaderboard, or None if the score is not high enough.
+    pub fn submit_score(&self, name: &str) -> Result<Option<usize>, String> {
+        if self.is_replaying() || self.is_versus() {
+            return Err("Scores of replays and versus games can not be submitted".to_string());
+        }
+        if self.is_assisted() {
+            return Err("Scores of games played by a bot can not be submitted".to_string());
+        }
+        let path = self.get_high_scores_path()?;
+        let mut game = self.game.lock().unwrap();
+        let high_score = game.take_high_score(name)
+            .ok_or("There is no finished game to submit".to_string())?;
+        let mode = game.get_mode();
+
+        let mut table = HighScoreTable::load(&path)?;
+        let position = table.submit(&mode, high_score.clone());
+        table.save(&path)?;
+
+        if position == Some(0) {
+            self.emitter.emit(GameEvent::NewRecord { mode: mode.clone(), position: 0, high_score });
+        }
+        Ok(position)
+    }
+
+    fn get_lifetime_stats_path(&self) -> Result<PathBuf, String> {
+        match &self.data_dir {
+            Some(data_dir) => Ok(data_dir.join(LIFETIME_STATS_FILE_NAME)),
+            None => Err("No directory available to store lifetime statistics".to_string())
+        }
+    }
+
+    pub fn get_lifetime_stats(&self) -> Result<LifetimeStats, String> {
+        LifetimeStats::load(&self.get_lifetime_stats_path()?)
+    }
+
+    pub fn reset_lifetime_stats(&self) -> Result<(), String> {
+        let lifetime_stats = LifetimeStats::default();
+        lifetime_stats.save(&self.get_lifetime_stats_path()?)?;
+        self.emitter.emit(GameEvent::LifetimeStats(lifetime_stats));
+        Ok(())
+    }
+
+    /// Add the statistics of the game to the lifetime statistics. Each game is only counted
+    /// once, games which have not been started or which were played by a bot are not counted.
+    fn record_lifetime_stats(&self, game: &mut Game) {
+        if self.is_assisted() {
+            return;
+        }
+        let Some(stats) = game.take_final_stats() else { return; };
+        let Ok(path) = self.get_lifetime_stats_path() else { return; };
+
+        let result = LifetimeStats::load(&path).and_then(|mut lifetime_stats| {
+            lifetime_stats.record_game(&game.get_mode(), &stats);
+            lifetime_stats.save(&path)?;
+            Ok(lifetime_stats)
+        });
+        match result {
+            Ok(lifetime_stats) => self.emitter.emit(GameEvent::LifetimeStats(lifetime_stats)),
+            Err(err) => println!("Could not update lifetime statistics: {}", err)
+        }
+    }
+
+    /// Stop the current game or replay before it is replaced. An unfinished game still counts
+    /// towards the lifetime statistics.
+    fn end_game(&self) {
+        {
+            let mut game = self.game.lock().unwrap();
+            if !self.is_replaying() {
+                self.record_lifetime_stats(&mut game);
+            }
+        }
+        self.stop_replay();
+        self.assisted.store(false, atomic::Ordering::SeqCst);
+    }
+
+    fn is_assisted(&self) -> bool {
+        self.assisted.load(atomic::Ordering::SeqCst)
+    }
+
+    /// Go back to the rules and the settings of the player, the game of the replay is replaced
+    /// by the caller.
+    fn stop_replay(&self) {
+        *self.replay_player.lock().unwrap() = None;
+        if let Some(rules) = self.player_rules.lock().unwrap().take() {
+            let mut game = self.game.lock().unwrap();
+            game.set_rules(&rules);
+            apply_settings(&mut game, &self.settings.lock().unwrap());
+        }
+    }
+
+    /// Start a new game, or a new match in versus mode.
+    pub fn reset(&self) {
+        if self.is_network_client() {
+            println!("The game is reset by the host");
+            return;
+        }
+        self.pause();
+        self.end_game();
+        let mut game = self.game.lock().unwrap();
+        match self.versus.lock().unwrap().as_mut() {
+            Some(versus) => versus.restart(&mut game),
+            None => game.reset()
+        }
+    }
+
+    /// Puzzles are played by a single player, so a versus match is stopped.
+    pub fn set_puzzle(&self, puzzle: Option<Puzzle>) -> Result<(), String> {
+        self.pause();
+        self.end_game();
+        self.end_versus();
+        let mut game = self.game.lock().unwrap();
+        game.set_puzzle(puzzle)
+    }
+
+    pub fn set_piece_set(&self, piece_set: PieceSet) {
+        self.pause();
+        self.end_game();
+        let mut game = self.game.lock().unwrap();
+        game.set_piece_set(piece_set.clone());
+        if let Some(versus) = self.versus.lock().unwrap().as_mut() {
+            versus.opponent.set_piece_set(piece_set);
+            versus.restart(&mut game);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use tetris_core::command::Command;
+use tetris_core::piece_set::PieceSet;
+use tetris_core::storage;
+
+pub const SETTINGS_VERSION: u32 = 1;
+pub const MAX_PREVIEW_COUNT: usize = 6;
+pub const MAX_START_LEVEL: i32 = 29;
+const MAX_DELAY: u32 = 1000; // Milliseconds
+const MAX_GARBAGE_DELAY: u32 = 10000; // Milliseconds
+
+/// Preferences of the player. Settings missing from the file get their default value.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub keybindings: BTreeMap<String, Vec<String>>, // Keys of each command, as KeyboardEvent.key
+    pub versus_keybindings: BTreeMap<String, Vec<String>>, // Keys of the second player in versus
+    pub das: u32, // Delayed auto shift: milliseconds a key is held before it repeats
+    pub arr: u32, // Auto repeat rate: milliseconds between repeats, 0 moves instantly
+    pub preview_count: usize, // Number of upcoming pieces that are shown
+    pub ghost: bool, // Show where the current piece lands
+    pub start_level: i32,
+    pub default_mode: String, // Built-in piece set played at startup
+    pub finesse_training: bool, // Pieces placed with a finesse fault have to be placed again
+    pub garbage_delay: u32, // Milliseconds before garbage received in versus can rise
+    pub garbage_messiness: f64, // Chance that the hole of a garbage row moves, 0 to 1
+    pub spectator_server: bool, // Broadcast the events of the game to spectators
+    pub spectator_port: u16,
+}
+
+fn make_keybindings(keybindings: &[(&str, Vec<&str>)]) -> BTreeMap<String, Vec<String>> {
+    keybindings.iter()
+        .map(|(command, keys)| {
+            (command.to_string(), keys.iter().map(|key| key.to_string()).collect())
+        })
+        .collect()
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let keybindings = [
+            ("left", vec!["ArrowLeft"]),
+            ("right", vec!["ArrowRight"]),
+            ("down", vec!["ArrowDown"]),
+            ("counter-clockwise", vec!["z", "Z"]),
+            ("clockwise", vec!["x", "X"]),
+            ("hard-drop", vec![" "]),
+        ];
+        let versus_keybindings = [
+            ("left", vec!["a", "A"]),
+            ("right", vec!["d", "D"]),
+            ("down", vec!["s", "S"]),
+            ("counter-clockwise", vec!["q", "Q"]),
+            ("clockwise", vec!["e", "E"]),
+            ("hard-drop", vec!["w", "W"]),
+        ];
+        Settings {
+            version: SETTINGS_VERSION,
+            keybindings: make_keybindings(&keybindings),
+            versus_keybindings: make_keybindings(&versus_keybindings),
+            das: 170,
+            arr: 50,
+            preview_count: 1,
+            ghost: true,
+            start_level: 0,
+            default_mode: "standard".to_string(),
+            finesse_training: false,
+            garbage_delay: 1000,
+            garbage_messiness: 0.,
+            spectator_server: false,
+            spectator_port: 9002,
+        }
+    }
+}
+
+impl Settings {
+    /// Load the settings from the given path. The default settings are returned if there is no
+    /// file yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Settings::default());
+        }
+        let value: serde_json::Value = storage::read_json(path)?;
+        let settings = Self::migrate(value)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        storage::write_json(path, self)
+    }
+
+    fn migrate(mut value: serde_json::Value) -> Result<Self, String> {
+        // Files without a version were written before the format was versioned
+        let version = value.get("version").and_then(|version| version.as_u64()).unwrap_or(0);
+        if version > SETTINGS_VERSION as u64 {
+            return Err(format!(
+                "Settings version {} is newer than the supported version {}", version, SETTINGS_VERSION
+            ));
+        }
+
+        // Settings added in later versions get their default value. Changes of existing
+        // settings need a conversion here, from the version they were introduced in.
+        if let Some(object) = value.as_object_mut() {
+            object.insert("version".to_string(), SETTINGS_VERSION.into());
+        }
+        serde_json::from_value(value).map_err(|err| format!("Invalid settings: {}", err))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != SETTINGS_VERSION {
+            return Err(format!("Unsupported settings version {}", self.version));
+        }
+
+        // Both players play on the same keyboard, so a key can only be bound once
+        let mut bound_keys = HashSet::new();
+        for (command, keys) in self.keybindings.iter().chain(&self.versus_keybindings) {
+            if Command::parse(command).is_none() {
+                return Err(format!("Unknown command '{}' in keybindings", command));
+            }
+            for key in keys {
+                if key.is_empty() || !bound_keys.insert(key) {
+                    return Err(format!("Invalid or duplicate key '{}' in keybindings", key));
+                }
+            }
+        }
+
+        if self.das > MAX_DELAY || self.arr > MAX_DELAY {
+            return Err(format!("DAS and ARR must be at most {} ms", MAX_DELAY));
+        }
+        if self.preview_count < 1 || self.preview_count > MAX_PREVIEW_COUNT {
+            return Err(format!("The preview count must be between 1 and {}", MAX_PREVIEW_COUNT));
+        }
+        if self.start_level < 0 || self.start_level > MAX_START_LEVEL {
+            return Err(format!("The start level must be between 0 and {}", MAX_START_LEVEL));
+        }
+        if self.garbage_delay > MAX_GARBAGE_DELAY {
+            return Err(format!("The garbage delay must be at most {} ms", MAX_GARBAGE_DELAY));
+        }
+        if !(0. ..=1.).contains(&self.garbage_messiness) {
+            return Err("The garbage messiness must be between 0 and 1".to_string());
+        }
+        if self.spectator_port == 0 {
+            return Err("The spectator port must be a port number".to_string());
+        }
+        if PieceSet::builtin(&self.default_mode).is_none() {
+            return Err(format!("Unknown default mode '{}'", self.default_mode));
+        }
+        Ok(())
+    }
+}
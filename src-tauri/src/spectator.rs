@@ -0,0 +1,131 @@
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tungstenite::{Message, WebSocket};
+
+use tetris_core::events::VersionedEvent;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+// Spectators which stop reading are dropped, so they can not block the other spectators
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+const MAX_REQUEST_SIZE: usize = 8192;
+/// Number of events which wait for the spectator server, newer events are dropped while the
+/// server is behind.
+pub const EVENT_BUFFER: usize = 4096;
+
+/// Page which shows the game of a spectator stream, e.g. as a browser source in OBS.
+const VIEWER_PAGE: &str = include_str!("spectator.html");
+
+/// A local server which broadcasts the events of the game as JSON, in the same format as they
+/// are sent to the interface, to every spectator connected with a WebSocket. Other requests get
+/// a page which shows the game. Spectators can only watch, anything they send is ignored. The
+/// server stops on drop.
+pub struct SpectatorServer {
+    port: u16,
+    stop: Arc<AtomicBool>,
+}
+
+impl SpectatorServer {
+    /// Listen on the given port. The events are received from the channel, and `on_connect` is
+    /// called when a spectator connects, to send the complete state of the game.
+    pub fn start(
+        port: u16,
+        events: mpsc::Receiver<VersionedEvent>,
+        on_connect: impl Fn() + Send + 'static) -> Result<Self, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .map_err(|err| format!("Could not start the spectator server on port {}: {}", port, err))?;
+        listener.set_nonblocking(true).map_err(|err| err.to_string())?;
+        println!("Spectator server listening on port {}", port);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_clone = stop.clone();
+        thread::spawn(move || {
+            let mut spectators: Vec<WebSocket<TcpStream>> = Vec::new();
+            let (accepted_sender, accepted) = mpsc::channel();
+            while !stop_clone.load(Ordering::SeqCst) {
+                // Each request is read on its own thread, so a slow request does not hold up
+                // the spectators
+                while let Ok((stream, address)) = listener.accept() {
+                    let accepted_sender = accepted_sender.clone();
+                    thread::spawn(move || match accept_spectator(stream) {
+                        Ok(Some(socket)) => {
+                            println!("Spectator connected from {}", address);
+                            let _ = accepted_sender.send(socket);
+                        }
+                        Ok(None) => {}
+                        Err(err) => println!("Could not accept spectator {}: {}", address, err),
+                    });
+                }
+                while let Ok(socket) = accepted.try_recv() {
+                    spectators.push(socket);
+                    on_connect();
+                }
+
+                while let Ok(event) = events.try_recv() {
+                    let Ok(text) = serde_json::to_string(&event) else { continue; };
+                    // Spectators which can not be reached anymore are dropped
+                    spectators.retain_mut(|socket| socket.send(Message::Text(text.clone())).is_ok());
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+        Ok(SpectatorServer { port, stop })
+    }
+
+    pub fn get_port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for SpectatorServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Upgrade the connection to a WebSocket, or answer with the viewer page if the request is no
+/// WebSocket request. Returns None if the page was sent.
+fn accept_spectator(stream: TcpStream) -> Result<Option<WebSocket<TcpStream>>, String> {
+    stream.set_nonblocking(false).map_err(|err| err.to_string())?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT)).map_err(|err| err.to_string())?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT)).map_err(|err| err.to_string())?;
+    let request = peek_request(&stream).map_err(|err| err.to_string())?;
+    if request.to_lowercase().contains("upgrade: websocket") {
+        return tungstenite::accept(stream).map(Some).map_err(|err| err.to_string());
+    }
+    send_viewer_page(stream).map_err(|err| err.to_string())?;
+    Ok(None)
+}
+
+/// The headers of the request, without removing them from the stream, so the WebSocket
+/// handshake can still read them. Fails if the headers are not complete before the timeout.
+fn peek_request(stream: &TcpStream) -> io::Result<String> {
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    let mut buffer = vec![0; MAX_REQUEST_SIZE];
+    loop {
+        let size = stream.peek(&mut buffer)?;
+        let request = String::from_utf8_lossy(&buffer[..size]);
+        if request.contains("\r\n\r\n") || size == 0 || size == MAX_REQUEST_SIZE {
+            return Ok(request.into_owned());
+        }
+        if Instant::now() > deadline {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "Incomplete request"));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn send_viewer_page(mut stream: TcpStream) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        VIEWER_PAGE.len(),
+        VIEWER_PAGE
+    )?;
+    stream.flush()
+}
@@ -0,0 +1,180 @@
+use tetris_core::bot::{Autoplayer, Bot, Weights};
+use tetris_core::command::{Command, CommandResult};
+use tetris_core::event_sink::EventSink;
+use tetris_core::events::GameEvent;
+use tetris_core::game::{Game, TICK_RATE};
+
+use crate::emitter::Emitter;
+
+/// How well the CPU opponent plays.
+#[derive(Clone, Copy, Debug)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            "expert" => Some(Difficulty::Expert),
+            _ => None,
+        }
+    }
+
+    /// The speed and skill of the CPU opponent at this difficulty.
+    pub fn get_cpu_settings(&self) -> CpuSettings {
+        let (pieces_per_second, noise) = match self {
+            Difficulty::Easy => (0.5, 3.0),
+            Difficulty::Medium => (1.0, 1.5),
+            Difficulty::Hard => (2.0, 0.5),
+            Difficulty::Expert => (3.0, 0.0),
+        };
+        CpuSettings { pieces_per_second, noise }
+    }
+}
+
+/// How the CPU opponent plays, see Difficulty for the presets.
+#[derive(Clone, Copy, Debug)]
+pub struct CpuSettings {
+    pub pieces_per_second: f64, // Maximum number of pieces per second
+    pub noise: f64, // Noise of the evaluations of the bot, more noise makes more mistakes
+}
+
+impl CpuSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.pieces_per_second.is_finite() && self.pieces_per_second > 0.) {
+            return Err("The pieces per second of the CPU have to be more than 0".to_string());
+        }
+        if !(self.noise.is_finite() && self.noise >= 0.) {
+            return Err("The noise of the CPU can not be negative".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A bot which plays the game of the second player.
+struct CpuOpponent {
+    bot: Bot,
+    ticks_per_piece: u64, // Minimum number of ticks between two placed pieces
+    pieces_placed: u64,
+    last_piece_tick: u64, // Tick at which the last piece was placed
+    held_drop: bool, // The bot wants to drop the piece, but it has to wait for the cap
+}
+
+impl CpuOpponent {
+    fn new(settings: CpuSettings) -> Self {
+        let mut bot = Bot::new(Weights::default());
+        bot.set_noise(settings.noise);
+        let ticks_per_piece = (TICK_RATE / settings.pieces_per_second).ceil() as u64;
+        CpuOpponent { bot, ticks_per_piece, pieces_placed: 0, last_piece_tick: 0, held_drop: false }
+    }
+
+    fn restart(&mut self) {
+        self.bot.discard_plan();
+        self.pieces_placed = 0;
+        self.last_piece_tick = 0;
+        self.held_drop = false;
+    }
+
+    /// Apply one command per tick like a player would. The bot moves the piece right away, but
+    /// the hard drop waits until enough ticks passed since the last piece was placed.
+    fn step(&mut self, game: &mut Game) {
+        // The piece may also have been locked by gravity
+        if game.get_pieces_placed() != self.pieces_placed {
+            self.pieces_placed = game.get_pieces_placed();
+            self.last_piece_tick = game.get_tick_count();
+            self.held_drop = false;
+        }
+        let can_drop = game.get_tick_count() >= self.last_piece_tick + self.ticks_per_piece;
+        let command = match self.held_drop {
+            true if can_drop => Command::HardDrop,
+            true => return,
+            false => match self.bot.next_command(game) {
+                Some(Command::HardDrop) if !can_drop => {
+                    self.held_drop = true;
+                    return;
+                }
+                Some(command) => command,
+                None => return,
+            },
+        };
+        self.held_drop = false;
+        match game.process_command(command) {
+            CommandResult::Accepted if command == Command::HardDrop => {
+                self.pieces_placed = game.get_pieces_placed();
+                self.last_piece_tick = game.get_tick_count();
+            }
+            CommandResult::Accepted => {}
+            _ => self.bot.discard_plan(),
+        }
+    }
+}
+
+/// The game of the second player in versus mode, next to the game of the first player. Lines
+/// cleared by a player send garbage to the other player, the first player to top out loses.
+/// The second player is either a person or a CPU opponent.
+pub struct Versus {
+    pub opponent: Game,
+    shared_seed: bool, // Both players get the same sequence of pieces
+    cpu: Option<CpuOpponent>,
+}
+
+impl Versus {
+    /// Make the game of the second player, with the same piece set as the game of the first
+    /// player.
+    pub fn new(game: &Game, emitter: &Emitter, shared_seed: bool) -> Self {
+        let mut opponent = Game::new(Box::new(emitter.for_player(1)));
+        opponent.set_piece_set(game.get_piece_set().clone());
+        Versus { opponent, shared_seed, cpu: None }
+    }
+
+    /// Make the game of a CPU opponent which plays with the given settings. The players get
+    /// different pieces.
+    pub fn against_cpu(game: &Game, emitter: &Emitter, settings: CpuSettings) -> Self {
+        Versus { cpu: Some(CpuOpponent::new(settings)), ..Versus::new(game, emitter, false) }
+    }
+
+    /// The second player is played by the CPU, so its game takes no commands.
+    pub fn is_against_cpu(&self) -> bool {
+        self.cpu.is_some()
+    }
+
+    /// Start a new match.
+    pub fn restart(&mut self, game: &mut Game) {
+        let seed = rand::random();
+        game.restart(seed);
+        self.opponent.restart(if self.shared_seed { seed } else { rand::random() });
+        if let Some(cpu) = self.cpu.as_mut() {
+            cpu.restart();
+        }
+    }
+
+    /// Forward the game of the second player a tick, after the game of the first player, and
+    /// exchange the garbage. Returns false when the match is over.
+    pub fn tick(&mut self, game: &mut Game, emitter: &Emitter) -> bool {
+        if !game.get_game_over() {
+            if let Some(cpu) = self.cpu.as_mut() {
+                cpu.step(&mut self.opponent);
+            }
+            self.opponent.tick();
+        }
+        // The achievements are only for the first player
+        self.opponent.drain_engine_events();
+        self.opponent.receive_garbage(game.take_outgoing_garbage());
+        game.receive_garbage(self.opponent.take_outgoing_garbage());
+
+        let winner = match (game.get_game_over(), self.opponent.get_game_over()) {
+            (false, false) => return true,
+            (true, true) => None,
+            (true, false) => Some(1),
+            (false, true) => Some(0),
+        };
+        emitter.emit(GameEvent::VersusOver { winner });
+        false
+    }
+}
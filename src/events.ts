@@ -0,0 +1,79 @@
+// Types of the events sent by the game on the "game_event" channel, see tetris-core/src/events.rs
+
+export const EVENT_VERSION = 1
+
+export interface TetrominoPayload {
+  name: string,
+  occupied_positions: [number, number][]
+}
+
+export interface HighScore {
+  name: string,
+  score: number,
+  lines: number,
+  level: number,
+  duration: number,
+  date: number,
+  seed: number
+}
+
+export interface Stats {
+  pieces_placed: number,
+  lines_cleared: number,
+  clears: Record<string, number>,
+  piece_distribution: Record<string, number>,
+  max_combo: number,
+  finesse_faults: number,
+  time_played: number,
+  pieces_per_second: number,
+  lines_per_minute: number
+}
+
+export interface LifetimeStats {
+  games_played: number,
+  total_lines: number,
+  total_tetrises: number,
+  hours_played: number,
+  best_pps: Record<string, number>
+}
+
+export interface Achievement {
+  id: string,
+  name: string,
+  description: string,
+  unlocked_at: number | null
+}
+
+export type GameEvent =
+  | { type: "current_tetromino" } & TetrominoPayload
+  | { type: "ghost_tetromino" } & TetrominoPayload
+  | { type: "next_tetromino" } & TetrominoPayload
+  | { type: "preview", tetrominoes: TetrominoPayload[] }
+  | { type: "board", board: string[][] }
+  | { type: "score", score: number }
+  | { type: "score_increase", points: number }
+  | { type: "level", level: number }
+  | { type: "stats" } & Stats
+  | { type: "game_over" }
+  | { type: "puzzle", name: string, description: string }
+  | { type: "puzzle_success", name: string }
+  | { type: "puzzle_failure", name: string }
+  | { type: "new_record", mode: string, position: number, high_score: HighScore }
+  | { type: "lifetime_stats" } & LifetimeStats
+  | { type: "achievement_unlocked" } & Achievement
+  | { type: "finesse_fault", piece: string, inputs: number, optimal: string[] }
+  | { type: "versus_over", winner: number | null }
+  | { type: "pending_garbage", lines: number, ready: number }
+
+// The player is 0, or 1 for the second player in versus mode
+export type VersionedEvent = { version: number, player: number } & GameEvent
+
+export type CommandResult =
+  | "accepted"
+  | "not_running"
+  | "paused"
+  | "game_over"
+  | "blocked"
+  | "unknown_command"
+  | "unknown_player"
+  | "forwarded"
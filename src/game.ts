@@ -13,6 +13,7 @@ export class Board {
   readonly n_cols: number;
   private board_without_tetromino: string[][];
   private tetromino: Tetromino;
+  private ghost: Tetromino;
   public board: string[][];
 
     constructor(n_rows: number, n_columns: number) {
@@ -20,6 +21,7 @@ export class Board {
       this.n_cols = n_columns;
       this.board_without_tetromino = this.makeEmptyBoard()
       this.tetromino = new Tetromino([], "_")
+      this.ghost = new Tetromino([], "_")
       this.board = this.copyBoard(this.board_without_tetromino)
     }
 
@@ -49,11 +51,37 @@ export class Board {
       this.drawBoard()
     }
 
+    // The ghost is drawn below the tetromino, so the tetromino is on top when they overlap
+    public setGhost(ghost: Tetromino) {
+      this.ghost = ghost
+      this.drawBoard()
+    }
+
     private drawBoard() {
       let board = this.copyBoard(this.board_without_tetromino)
+      for (let pos of this.ghost.positions) {
+        board[pos[0]][pos[1]] = this.ghost.colorCode
+      }
       for (let pos of this.tetromino.positions) {
         board[pos[0]][pos[1]] = this.tetromino.colorCode
       }
       this.board = board
     }
 }
+
+export interface Settings {
+  version: number,
+  keybindings: Record<string, string[]>, // Keys of each command, as KeyboardEvent.key
+  versus_keybindings: Record<string, string[]>, // Keys of the second player in versus mode
+  das: number, // Milliseconds a key is held before it repeats
+  arr: number, // Milliseconds between repeats, 0 moves as far as possible at once
+  preview_count: number,
+  ghost: boolean,
+  start_level: number,
+  default_mode: string,
+  finesse_training: boolean,
+  garbage_delay: number, // Milliseconds before garbage received in versus can rise
+  garbage_messiness: number, // Chance that the hole of a garbage row moves, 0 to 1
+  spectator_server: boolean, // Broadcast the game to spectators
+  spectator_port: number
+}
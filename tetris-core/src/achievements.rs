@@ -0,0 +1,152 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stats::ClearType;
+use crate::storage;
+
+const BUILTIN_ACHIEVEMENTS: &str = include_str!("../../achievements/default.json");
+
+/// What the player has to do in a game to unlock an achievement.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    Clear { clear_type: ClearType },
+    Combo { combo: i32 },
+    PerfectClear,
+    Level { level: i32 },
+    Lines { lines: i32 }, // Lines cleared in a single game
+    Sprint { lines: i32, seconds: f64 }, // Lines cleared within the given time
+}
+
+/// Something that happened in a game which can unlock achievements.
+pub enum EngineEvent {
+    PieceLocked {
+        clear_type: Option<ClearType>,
+        combo: i32,
+        perfect_clear: bool,
+        total_lines: i32,
+        time_played: f64, // Seconds
+    },
+    LevelUp { level: i32 },
+}
+
+impl Condition {
+    fn is_met(&self, event: &EngineEvent) -> bool {
+        match (self, event) {
+            (Condition::Clear { clear_type }, EngineEvent::PieceLocked { clear_type: cleared, .. }) => {
+                *cleared == Some(*clear_type)
+            }
+            (Condition::Combo { combo }, EngineEvent::PieceLocked { combo: current, .. }) => {
+                current >= combo
+            }
+            (Condition::PerfectClear, EngineEvent::PieceLocked { perfect_clear, .. }) => {
+                *perfect_clear
+            }
+            (Condition::Level { level }, EngineEvent::LevelUp { level: reached }) => {
+                reached >= level
+            }
+            (Condition::Lines { lines }, EngineEvent::PieceLocked { total_lines, .. }) => {
+                total_lines >= lines
+            }
+            (
+                Condition::Sprint { lines, seconds },
+                EngineEvent::PieceLocked { total_lines, time_played, .. }
+            ) => total_lines >= lines && time_played <= seconds,
+            _ => false
+        }
+    }
+}
+
+/// Definition of an achievement. The built-in achievements are defined in
+/// achievements/default.json, more can be added in the same format.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub condition: Condition,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AchievementStatus {
+    #[serde(flatten)]
+    pub achievement: Achievement,
+    pub unlocked_at: Option<u64>, // Seconds since the unix epoch, None if not unlocked yet
+}
+
+/// Keeps track of which achievements the player has unlocked.
+pub struct AchievementTracker {
+    definitions: Vec<Achievement>,
+    unlocked: HashMap<String, u64>, // Time of unlocking by achievement id
+}
+
+impl AchievementTracker {
+    /// Make a tracker with the built-in achievements and the achievements defined in the JSON
+    /// files in the given directory, if it exists.
+    pub fn new(custom_dir: Option<&Path>) -> Result<Self, String> {
+        let mut definitions: Vec<Achievement> = serde_json::from_str(BUILTIN_ACHIEVEMENTS)
+            .map_err(|err| format!("Invalid built-in achievements: {}", err))?;
+
+        if let Some(custom_dir) = custom_dir.filter(|dir| dir.is_dir()) {
+            let entries = fs::read_dir(custom_dir)
+                .map_err(|err| format!("Could not read {}: {}", custom_dir.display(), err))?;
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_some_and(|extension| extension == "json") {
+                    let custom: Vec<Achievement> = storage::read_json(&path)?;
+                    definitions.extend(custom);
+                }
+            }
+        }
+
+        let mut ids = HashSet::new();
+        for achievement in &definitions {
+            if !ids.insert(achievement.id.as_str()) {
+                return Err(format!("Duplicate achievement id '{}'", achievement.id));
+            }
+        }
+
+        Ok(AchievementTracker { definitions, unlocked: HashMap::new() })
+    }
+
+    /// Load the unlocked achievements from the given path, if the file exists.
+    pub fn load_unlocked(&mut self, path: &Path) -> Result<(), String> {
+        if path.exists() {
+            self.unlocked = storage::read_json(path)?;
+        }
+        Ok(())
+    }
+
+    pub fn save_unlocked(&self, path: &Path) -> Result<(), String> {
+        storage::write_json(path, &self.unlocked)
+    }
+
+    /// Unlock the achievements whose condition is met by the event. Returns the achievements
+    /// which were unlocked, with the given time of unlocking.
+    pub fn handle(&mut self, event: &EngineEvent, time: u64) -> Vec<AchievementStatus> {
+        let mut newly_unlocked = Vec::new();
+        for achievement in &self.definitions {
+            if !self.unlocked.contains_key(&achievement.id) && achievement.condition.is_met(event) {
+                self.unlocked.insert(achievement.id.clone(), time);
+                newly_unlocked.push(AchievementStatus {
+                    achievement: achievement.clone(),
+                    unlocked_at: Some(time),
+                });
+            }
+        }
+        newly_unlocked
+    }
+
+    pub fn get_status(&self) -> Vec<AchievementStatus> {
+        self.definitions.iter()
+            .map(|achievement| AchievementStatus {
+                achievement: achievement.clone(),
+                unlocked_at: self.unlocked.get(&achievement.id).copied(),
+            })
+            .collect()
+    }
+}
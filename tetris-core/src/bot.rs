@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+
+use rand::Rng;
+
+use crate::command::Command;
+use crate::game::{Board, Game, BOARD_COLS, BOARD_ROWS};
+use crate::placements::{apply_command, reachable_placements, Placement};
+use crate::tetromino::Tetromino;
+
+/// Weights of the features of a board, for the evaluation of a placement. Positive weights
+/// reward a feature, negative weights penalize it.
+#[derive(Clone, Debug)]
+pub struct Weights {
+    pub aggregate_height: f64, // Sum of the heights of the columns
+    pub lines_cleared: f64,
+    pub holes: f64, // Empty cells with a block above them
+    pub bumpiness: f64, // Sum of the height differences of neighbouring columns
+    pub wells: f64, // Sum of the depths of columns which are lower than both neighbours
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            aggregate_height: -0.51,
+            lines_cleared: 0.76,
+            holes: -0.36,
+            bumpiness: -0.18,
+            wells: -0.1,
+        }
+    }
+}
+
+fn column_heights(board: &Board) -> [i32; BOARD_COLS] {
+    let mut heights = [0; BOARD_COLS];
+    for (j, height) in heights.iter_mut().enumerate() {
+        if let Some(top) = (0..BOARD_ROWS).find(|i| board[*i][j] != '_') {
+            *height = (BOARD_ROWS - top) as i32;
+        }
+    }
+    heights
+}
+
+/// Lock the tetromino on a copy of the board and clear the full rows. Returns the resulting
+/// board and the number of cleared rows.
+pub fn lock_on_board(board: &Board, tetromino: &Tetromino) -> (Board, i32) {
+    let mut board = *board;
+    for (i, j) in tetromino.get_occupied_positions() {
+        board[*i as usize][*j as usize] = tetromino.get_shape_name();
+    }
+
+    let remaining: Vec<[char; BOARD_COLS]> = board.iter()
+        .filter(|row| row.contains(&'_'))
+        .copied()
+        .collect();
+    let lines_cleared = (BOARD_ROWS - remaining.len()) as i32;
+    let mut cleared = [['_'; BOARD_COLS]; BOARD_ROWS];
+    cleared[lines_cleared as usize..].copy_from_slice(&remaining);
+    (cleared, lines_cleared)
+}
+
+impl Weights {
+    /// The value of the board after a placement which cleared the given number of lines.
+    pub fn evaluate(&self, board: &Board, lines_cleared: i32) -> f64 {
+        let heights = column_heights(board);
+        let aggregate_height: i32 = heights.iter().sum();
+        let bumpiness: i32 = heights.windows(2).map(|pair| (pair[0] - pair[1]).abs()).sum();
+
+        let mut holes = 0;
+        for (j, height) in heights.iter().enumerate() {
+            let top = BOARD_ROWS - *height as usize;
+            holes += (top..BOARD_ROWS).filter(|i| board[*i][j] == '_').count() as i32;
+        }
+
+        // The walls count as columns of the full height
+        let mut wells = 0;
+        for j in 0..BOARD_COLS {
+            let left = if j == 0 { BOARD_ROWS as i32 } else { heights[j - 1] };
+            let right = heights.get(j + 1).copied().unwrap_or(BOARD_ROWS as i32);
+            wells += (left.min(right) - heights[j]).max(0);
+        }
+
+        self.aggregate_height * aggregate_height as f64
+            + self.lines_cleared * lines_cleared as f64
+            + self.holes * holes as f64
+            + self.bumpiness * bumpiness as f64
+            + self.wells * wells as f64
+    }
+}
+
+/// Plays the game by choosing the placement with the best evaluation for each new piece.
+pub struct Bot {
+    weights: Weights,
+    noise: f64, // Maximum random value added to or subtracted from each evaluation
+    plan: VecDeque<Command>, // Remaining commands for the current piece
+    expected: Option<Tetromino>, // The current piece as it should be before the next command
+}
+
+impl Bot {
+    pub fn new(weights: Weights) -> Self {
+        Bot { weights, noise: 0.0, plan: VecDeque::new(), expected: None }
+    }
+
+    /// Add a random value between -noise and noise to each evaluation, so the bot sometimes
+    /// misses the best placement. A noise of 0 always plays the best placement.
+    pub fn set_noise(&mut self, noise: f64) {
+        self.noise = noise.max(0.0);
+    }
+
+    /// The placement of the current piece with the best evaluation, or None if the piece can
+    /// not be placed.
+    pub fn choose_placement(&self, game: &Game) -> Option<Placement> {
+        let board = game.get_board();
+        reachable_placements(board, game.get_current_tetromino()).into_iter()
+            .map(|placement| {
+                let (after, lines_cleared) = lock_on_board(board, &placement.tetromino);
+                let mut evaluation = self.weights.evaluate(&after, lines_cleared);
+                if self.noise > 0.0 {
+                    evaluation += rand::thread_rng().gen_range(-self.noise..self.noise);
+                }
+                (evaluation, placement)
+            })
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, placement)| placement)
+    }
+}
+
+/// Plays the game one command at a time, like the built-in bot or an external bot.
+pub trait Autoplayer: Send {
+    /// The next command to apply to the game, or None if there is nothing to do yet.
+    fn next_command(&mut self, game: &Game) -> Option<Command>;
+
+    /// Make a new plan for the current piece, e.g. because gravity blocked the planned command.
+    fn discard_plan(&mut self);
+
+    /// The error which made the player stop playing, if any.
+    fn get_error(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl Autoplayer for Bot {
+    /// A new plan is made for each new piece, and when the piece is not where the plan expects
+    /// it, e.g. because gravity moved it down.
+    fn next_command(&mut self, game: &Game) -> Option<Command> {
+        if game.get_game_over() {
+            return None;
+        }
+        let current = game.get_current_tetromino();
+        let on_plan = self.expected.as_ref().is_some_and(|expected| {
+            expected.get_shape_name() == current.get_shape_name()
+                && expected.get_orientation() == current.get_orientation()
+                && expected.get_occupied_positions() == current.get_occupied_positions()
+        });
+        if !on_plan {
+            self.plan = self.choose_placement(game)
+                .map(|placement| placement.commands.into())
+                .unwrap_or_default();
+        }
+
+        let command = self.plan.pop_front()?;
+        self.expected = match command {
+            Command::HardDrop => None, // The next piece needs a new plan
+            _ => apply_command(game.get_board(), current, command)
+        };
+        Some(command)
+    }
+
+    fn discard_plan(&mut self) {
+        self.expected = None;
+    }
+}
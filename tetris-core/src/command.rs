@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// A command of the player. Commands are serialized in kebab case, e.g. "hard-drop".
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Command {
+    Down,
+    Left,
+    Right,
+    Clockwise,
+    CounterClockwise,
+    HardDrop,
+}
+
+impl Command {
+    /// Parse a command from its serialized name. Returns None for unknown commands.
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::from(name)).ok()
+    }
+}
+
+/// Outcome of a command of the player, which tells why the command was not applied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandResult {
+    Accepted,
+    NotRunning, // The game has not been started, or a replay is being watched
+    Paused,
+    GameOver,
+    Blocked, // The piece can not move or rotate that way
+    UnknownCommand,
+    UnknownPlayer, // There is no game of the player, e.g. the second player outside versus mode
+    Forwarded, // Sent to the host of the network game, which may still reject it
+}
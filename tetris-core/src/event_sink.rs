@@ -0,0 +1,63 @@
+use std::sync::{Arc, Mutex};
+
+use crate::events::GameEvent;
+
+/// Receives the events of the game, e.g. to update the interface.
+pub trait EventSink: Send {
+    fn emit(&self, event: GameEvent);
+}
+
+/// Forwards events to another sink, unless it is muted.
+pub struct MutableSink {
+    sink: Box<dyn EventSink>,
+    muted: bool, // Nothing is forwarded while muted
+}
+
+impl MutableSink {
+    pub fn new(sink: Box<dyn EventSink>) -> Self {
+        MutableSink { sink, muted: false }
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+}
+
+impl EventSink for MutableSink {
+    fn emit(&self, event: GameEvent) {
+        if !self.muted {
+            self.sink.emit(event);
+        }
+    }
+}
+
+/// Discards all events, for interfaces which read the state of the game directly.
+pub struct NullSink;
+
+impl EventSink for NullSink {
+    fn emit(&self, _event: GameEvent) {}
+}
+
+/// Keeps the events in memory, to drive a game without an interface, e.g. in tests. Clones
+/// share the recorded events, so a clone can be given to the game.
+#[derive(Clone, Default)]
+pub struct RecordingSink {
+    events: Arc<Mutex<Vec<GameEvent>>>,
+}
+
+impl RecordingSink {
+    pub fn new() -> Self {
+        RecordingSink::default()
+    }
+
+    /// Take the events which were recorded since the last call.
+    pub fn take_events(&self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events.lock().unwrap())
+    }
+}
+
+impl EventSink for RecordingSink {
+    fn emit(&self, event: GameEvent) {
+        self.events.lock().unwrap().push(event);
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+use crate::achievements::AchievementStatus;
+use crate::command::Command;
+use crate::game::Board;
+use crate::high_scores::HighScore;
+use crate::lifetime::LifetimeStats;
+use crate::stats::Stats;
+use crate::tetromino::Tetromino;
+
+/// Version of the format of the events. It is increased when an event is changed in a way that
+/// existing consumers can not handle, i.e. when anything other than a new event is added.
+pub const EVENT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TetrominoPayload {
+    pub name: char,
+    pub occupied_positions: Vec<(i32, i32)>, // (row, column) positions on the board
+}
+
+impl From<&Tetromino> for TetrominoPayload {
+    fn from(tetromino: &Tetromino) -> Self {
+        TetrominoPayload {
+            name: tetromino.get_shape_name(),
+            occupied_positions: tetromino.get_occupied_positions().clone(),
+        }
+    }
+}
+
+/// Every event sent by the game. Events are serialized as an object with the name of the event
+/// in snake case as "type", next to the fields of the event, e.g.
+/// `{"type": "score", "score": 1200}`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// The piece controlled by the player moved, rotated or was replaced by a new piece.
+    CurrentTetromino(TetrominoPayload),
+    /// Where the current piece lands when it is dropped.
+    GhostTetromino(TetrominoPayload),
+    /// The piece after the current piece, positioned in the top left corner.
+    NextTetromino(TetrominoPayload),
+    /// The pieces after the next piece, positioned in the top left corner.
+    Preview { tetrominoes: Vec<TetrominoPayload> },
+    /// The locked cells of the board, '_' for an empty cell and the piece name otherwise.
+    Board { board: Box<Board> },
+    Score { score: i32 },
+    /// Points scored by clearing lines.
+    ScoreIncrease { points: i32 },
+    Level { level: i32 },
+    /// Statistics of the game in progress, sent at a limited rate.
+    Stats(Stats),
+    GameOver,
+    /// A puzzle was started.
+    Puzzle { name: String, description: String },
+    PuzzleSuccess { name: String },
+    PuzzleFailure { name: String },
+    /// The finished game has the best score of its mode.
+    NewRecord { mode: String, position: usize, high_score: HighScore },
+    LifetimeStats(LifetimeStats),
+    AchievementUnlocked(AchievementStatus),
+    /// The piece was placed with more inputs than needed. The optimal inputs do not include the
+    /// hard drop.
+    FinesseFault { piece: char, inputs: u32, optimal: Vec<Command> },
+    /// A player topped out in versus mode. The winner is None if both players topped out at the
+    /// same time.
+    VersusOver { winner: Option<usize> },
+    /// Garbage received from the opponent which did not rise yet. The ready lines rise when the
+    /// next piece is locked without clearing lines, the others are still delayed.
+    PendingGarbage { lines: u32, ready: u32 },
+}
+
+/// An event together with the version of the event format and the player whose game sent it,
+/// as it is sent to consumers: `{"version": 1, "player": 0, "type": "score", "score": 1200}`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VersionedEvent {
+    pub version: u32,
+    #[serde(default)]
+    pub player: usize, // 0 for the first player, and for games with a single player
+    #[serde(flatten)]
+    pub event: GameEvent,
+}
+
+impl VersionedEvent {
+    pub fn new(event: GameEvent, player: usize) -> Self {
+        VersionedEvent { version: EVENT_VERSION, player, event }
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+use crate::command::Command;
+use crate::game::{check_move, rotate_on_board, spawn_tetromino, Board, BOARD_ROWS, BOARD_COLS};
+use crate::tetromino::{Rotation, Tetromino};
+
+const FINESSE_COMMANDS: [Command; 4] = [
+    Command::Left, Command::Right, Command::Clockwise, Command::CounterClockwise
+];
+
+fn try_move(board: &Board, tetromino: &Tetromino, step: (i32, i32)) -> Option<Tetromino> {
+    check_move(board, tetromino, &step).ok().map(|_| {
+        let mut moved = tetromino.clone();
+        moved.move_pos(step);
+        moved
+    })
+}
+
+fn footprint(positions: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    // The cells of a piece relative to its top row, which is the same for every row the piece
+    // is dropped to
+    let top = positions.iter().map(|pos| pos.0).min().unwrap_or(0);
+    let mut cells: Vec<(i32, i32)> = positions.iter().map(|(i, j)| (i - top, *j)).collect();
+    cells.sort();
+    cells
+}
+
+/// Whether the piece can move straight up from where it was placed to the top of the board, so
+/// it could have been dropped into place.
+fn droppable(board: &Board, placed: &Tetromino) -> bool {
+    let mut tetromino = placed.clone();
+    while tetromino.get_occupied_positions().iter().all(|pos| pos.0 > 0) {
+        match try_move(board, &tetromino, (-1, 0)) {
+            Some(moved) => tetromino = moved,
+            None => return false,
+        }
+    }
+    true
+}
+
+/// A shortest sequence of moves and rotations which brings a piece from its spawn position above
+/// the position where it was placed on the given board, as if the board was empty. The hard drop
+/// is not included. Returns None if the placement can not be reached by dropping the piece, e.g.
+/// because it was tucked or spun into place.
+pub fn optimal_inputs(board: &Board, placed: &Tetromino) -> Option<Vec<Command>> {
+    if !droppable(board, placed) {
+        return None;
+    }
+    let board = [['_'; BOARD_COLS]; BOARD_ROWS];
+    let target = footprint(placed.get_occupied_positions());
+    let start = spawn_tetromino(placed.get_shape().in_spawn_state());
+
+    // Breadth first search over the positions reachable from the spawn position
+    let mut paths = HashMap::new();
+    paths.insert(start.get_occupied_positions().clone(), Vec::new());
+    let mut queue = VecDeque::new();
+    queue.push_back(start);
+
+    while let Some(tetromino) = queue.pop_front() {
+        let path = paths[tetromino.get_occupied_positions()].clone();
+        if footprint(tetromino.get_occupied_positions()) == target {
+            return Some(path);
+        }
+        for command in FINESSE_COMMANDS {
+            let next = match command {
+                Command::Left => try_move(&board, &tetromino, (0, -1)),
+                Command::Right => try_move(&board, &tetromino, (0, 1)),
+                Command::Clockwise => rotate_on_board(&board, &tetromino, Rotation::Clockwise),
+                _ => rotate_on_board(&board, &tetromino, Rotation::CounterClockwise)
+            };
+            let Some(next) = next else { continue; };
+            if let Entry::Vacant(entry) = paths.entry(next.get_occupied_positions().clone()) {
+                let mut next_path = path.clone();
+                next_path.push(command);
+                entry.insert(next_path);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
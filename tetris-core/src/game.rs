@@ -0,0 +1,1205 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::achievements::EngineEvent;
+use crate::command::{Command, CommandResult};
+use crate::event_sink::{EventSink, MutableSink};
+use crate::events::{GameEvent, TetrominoPayload};
+use crate::finesse;
+use crate::garbage::{GarbageQueue, GarbageSettings};
+use crate::high_scores::HighScore;
+use crate::piece_set::PieceSet;
+use crate::puzzle::{Puzzle, PuzzleState, PuzzleStatus};
+use crate::replay::{GameRules, Replay, ReplayInput, REPLAY_VERSION};
+use crate::stats::{ClearType, Stats, StatsCollector};
+use crate::storage;
+use crate::tetromino::{Rotation, Tetromino, TetrominoShape, TetrominoShapeGenerator};
+
+pub const BOARD_ROWS: usize = 21;
+pub const BOARD_COLS: usize = 10;
+pub const TICK_RATE: f64 = 60.0; // Ticks per second
+pub const SAVE_VERSION: u32 = 1;
+const STATS_INTERVAL: u64 = 30; // Ticks between two stats events
+
+pub type Board = [[char; BOARD_COLS]; BOARD_ROWS];
+
+/// Position of the top left corner of the grid of a new piece: at the top of the board,
+/// centered horizontally and moved by the spawn offset of the piece.
+pub fn spawn_position(size: usize, spawn_offset: (i32, i32)) -> (i32, i32) {
+    let col = (BOARD_COLS as i32 - size as i32) / 2;
+    (spawn_offset.0, col + spawn_offset.1)
+}
+
+pub fn spawn_tetromino(shape: TetrominoShape) -> Tetromino {
+    let mut tetromino = Tetromino::new((0, 0), shape);
+    tetromino.move_pos(spawn_position(tetromino.get_size(), tetromino.get_spawn_offset()));
+    tetromino
+}
+
+#[derive(Debug)]
+pub enum MoveNotAllowedError {
+    // Error for illegal moves. If the position is too far left or right, it contains a number
+    // describing the number of blocks so that the position can be adjusted accordingly.
+    TooFarLeft(i32),
+    TooFarRight(i32),
+    TooFarUp,
+    TooFarDown,
+    OverlapsWithOccupied,
+}
+
+/// Check if the tetromino can be moved by the given (row, column) step on the board.
+pub fn check_move(
+    board: &Board,
+    tetromino: &Tetromino,
+    step: &(i32, i32)) -> Result<(), MoveNotAllowedError> {
+
+    // Check if tetromino goes too far left or right.
+    // This is checked first, so that the position can be adjusted
+    let mut largest_dist = 0;
+    let mut err: Option<MoveNotAllowedError> = None;
+    for occupied_pos in tetromino.get_occupied_positions() {
+        let pos_after_move = (occupied_pos.0 + step.0, occupied_pos.1 + step.1);
+        if pos_after_move.1 < 0 {
+            let dist = -pos_after_move.1;
+            if dist > largest_dist {
+                largest_dist = dist;
+                err = Some(MoveNotAllowedError::TooFarLeft(-dist))
+            }
+        } else if pos_after_move.1 >= BOARD_COLS as i32 {
+            let dist = pos_after_move.1 + 1 - BOARD_COLS as i32 ;
+            if dist > largest_dist {
+                largest_dist = dist;
+                err = Some(MoveNotAllowedError::TooFarRight(dist))
+            }
+        }
+    }
+    if let Some(err_value) = err { return Err(err_value); }
+
+    // Then check if it is too far down, or colliding with an already occupied spot.
+    // This will not be adjusted and means the move is not valid.
+    for occupied_pos in tetromino.get_occupied_positions() {
+        let pos_after_move = (occupied_pos.0 + step.0, occupied_pos.1 + step.1);
+        if pos_after_move.0 < 0 {
+            return Err(MoveNotAllowedError::TooFarUp)
+        }
+        else if pos_after_move.0 >= BOARD_ROWS as i32 {
+            return Err(MoveNotAllowedError::TooFarDown)
+        }
+        else if board[pos_after_move.0 as usize][pos_after_move.1 as usize] != '_' {
+             // This has to be checked last. If a tetromino is (partly) out of the board and
+            // overlaps with an occupied spot, it should first be addressed that the
+            // tetromino is not completely on the board.
+            return Err(MoveNotAllowedError::OverlapsWithOccupied);
+        }
+    }
+    Ok(())
+}
+
+/// Rotate the tetromino in the given direction on the board. If the rotated tetromino sticks
+/// out of the side of the board it is pushed back, otherwise the kicks of the piece are tried in
+/// order. Returns None if the tetromino can not be rotated.
+pub fn rotate_on_board(board: &Board, tetromino: &Tetromino, direction: Rotation) -> Option<Tetromino> {
+    let mut tetromino = tetromino.clone();
+    tetromino.rotate(direction);
+
+    // Check if position after rotation is valid
+    match check_move(board, &tetromino, &(0, 0)) {
+        Ok(_) => return Some(tetromino),
+        Err(MoveNotAllowedError::TooFarLeft(x)) | Err(MoveNotAllowedError::TooFarRight(x)) => {
+            let step = (0, -x);
+            if check_move(board, &tetromino, &step).is_ok() {
+                tetromino.move_pos(step);
+                return Some(tetromino);
+            }
+        }
+        Err(_) => {}
+    }
+
+    // Try the offsets in the kick table of the rotated tetromino in order, until one fits
+    let kick = tetromino.get_kicks().iter()
+        .find(|step| check_move(board, &tetromino, step).is_ok())
+        .copied()?;
+    tetromino.move_pos(kick);
+    Some(tetromino)
+}
+
+/// The complete state of a game, to save a game and resume it later.
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    version: u32,
+    board: [[char; BOARD_COLS]; BOARD_ROWS],
+    current_tetromino: Tetromino,
+    next_tetromino: Tetromino,
+    tetromino_shape_generator: TetrominoShapeGenerator,
+    piece_set: PieceSet,
+    seed: u64,
+    level: i32,
+    total_lines_cleared: i32,
+    score: i32,
+    wait_ticks_remaining: i32,
+    game_over: bool,
+    last_move_was_rotation: bool,
+    tick_count: u64,
+    pieces_placed: u64,
+    puzzle: Option<PuzzleState>,
+    recorded_inputs: Vec<ReplayInput>,
+    #[serde(default)]
+    stats: StatsCollector,
+    #[serde(default)]
+    piece_inputs: u32,
+    #[serde(default)]
+    start_level: i32,
+    #[serde(default)]
+    finesse_training: bool,
+}
+
+impl SavedGame {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let saved_game: SavedGame = storage::read_json(path)?;
+        if saved_game.version != SAVE_VERSION {
+            return Err(format!("Unsupported saved game version {}", saved_game.version));
+        }
+        Ok(saved_game)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        storage::write_json(path, self)
+    }
+}
+
+/// Describes what happened when a tetromino was locked on the board.
+pub struct LockResult {
+    pub shape_name: char,
+    pub lines_cleared: i32,
+    pub t_spin: bool,
+    pub perfect_clear: bool, // The board is empty after clearing the lines
+}
+
+pub struct Game {
+    board: [[char; BOARD_COLS]; BOARD_ROWS],
+    current_tetromino: Tetromino,
+    next_tetromino: Tetromino,
+    tetromino_shape_generator: TetrominoShapeGenerator,
+    piece_set: PieceSet,
+    seed: u64,
+
+    level: i32,
+    start_level: i32,
+    total_lines_cleared: i32,
+    score: i32,
+    wait_ticks_remaining: i32,
+    game_over: bool,
+    last_move_was_rotation: bool,
+    tick_count: u64,
+    pieces_placed: u64,
+    preview_count: usize, // Number of upcoming pieces sent to the interface
+    finesse_training: bool, // A piece placed with a finesse fault has to be placed again
+
+    puzzle: Option<PuzzleState>,
+    recorded_inputs: Vec<ReplayInput>,
+    high_score_submitted: bool,
+    stats: StatsCollector,
+    stats_recorded: bool, // The statistics were added to the lifetime statistics
+    piece_inputs: u32, // Moves and rotations used for the current piece
+    garbage: GarbageQueue, // Garbage received from the opponent which did not rise yet
+    outgoing_garbage: u32, // Lines of garbage for the opponent, until they are taken
+    engine_events: Vec<EngineEvent>, // Events for the achievements, until they are taken
+
+    emitter: MutableSink
+}
+
+impl Game {
+    pub fn new(sink: Box<dyn EventSink>) -> Self {
+        let board = [['_'; BOARD_COLS]; BOARD_ROWS];
+        let piece_set = PieceSet::standard();
+        let seed = rand::random();
+        let mut tetromino_shape_generator = TetrominoShapeGenerator::new(&piece_set, seed);
+        let mut game =  Game {
+            board,
+            current_tetromino: spawn_tetromino(tetromino_shape_generator.make_random()),
+            next_tetromino: Tetromino::new(
+                (0, 0),
+                tetromino_shape_generator.make_random()
+            ),
+            tetromino_shape_generator,
+            piece_set,
+            seed,
+            level: 0,
+            start_level: 0,
+            total_lines_cleared: 0,
+            score: 0,
+            wait_ticks_remaining: 0, // Dummy value
+            game_over: false,
+            last_move_was_rotation: false,
+            tick_count: 0,
+            pieces_placed: 0,
+            preview_count: 1,
+            finesse_training: false,
+            puzzle: None,
+            recorded_inputs: Vec::new(),
+            high_score_submitted: false,
+            stats: StatsCollector::default(),
+            stats_recorded: false,
+            piece_inputs: 0,
+            garbage: GarbageQueue::new(seed, GarbageSettings::default()),
+            outgoing_garbage: 0,
+            engine_events: Vec::new(),
+            emitter: MutableSink::new(sink),
+        };
+        game.reset_wait_ticks();
+        game
+    }
+
+    pub fn get_game_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn get_tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    pub fn get_pieces_placed(&self) -> u64 {
+        self.pieces_placed
+    }
+
+    /// The frozen blocks on the board, '_' for an empty cell.
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_current_tetromino(&self) -> &Tetromino {
+        &self.current_tetromino
+    }
+
+    pub fn get_next_tetromino(&self) -> &Tetromino {
+        &self.next_tetromino
+    }
+
+    /// Names of the given number of upcoming pieces, starting with the current piece.
+    pub fn get_queue(&self, length: usize) -> Vec<char> {
+        let mut queue = vec![self.current_tetromino.get_shape_name(), self.next_tetromino.get_shape_name()];
+        queue.extend(
+            self.tetromino_shape_generator.preview(length.saturating_sub(2)).into_iter()
+                .map(|shape| Tetromino::new((0, 0), shape).get_shape_name())
+        );
+        queue.truncate(length);
+        queue
+    }
+
+    /// Number of consecutive line clears after the first, -1 if the last piece cleared nothing.
+    pub fn get_combo(&self) -> i32 {
+        self.stats.get_combo()
+    }
+
+    /// The last clear was a Tetris or a T-spin clear, so the next one gets the back-to-back bonus.
+    pub fn get_back_to_back(&self) -> bool {
+        self.garbage.get_back_to_back()
+    }
+
+    pub fn get_score(&self) -> i32 {
+        self.score
+    }
+
+    pub fn get_level(&self) -> i32 {
+        self.level
+    }
+
+    pub fn get_lines_cleared(&self) -> i32 {
+        self.total_lines_cleared
+    }
+
+    /// Time played in seconds
+    pub fn get_duration(&self) -> f64 {
+        self.tick_count as f64 / TICK_RATE
+    }
+
+    /// Add an attack of the opponent to the garbage queue. After the delay of the garbage
+    /// settings it rises when a piece is locked without clearing lines.
+    pub fn receive_garbage(&mut self, lines: u32) {
+        if lines > 0 {
+            self.garbage.receive(lines);
+            self.emit_pending_garbage();
+        }
+    }
+
+    pub fn set_garbage_settings(&mut self, settings: GarbageSettings) {
+        self.garbage.set_settings(settings);
+    }
+
+    /// Take the lines of garbage sent to the opponent since the last call.
+    pub fn take_outgoing_garbage(&mut self) -> u32 {
+        std::mem::take(&mut self.outgoing_garbage)
+    }
+
+    /// Take the events which happened since the last call.
+    pub fn drain_engine_events(&mut self) -> Vec<EngineEvent> {
+        std::mem::take(&mut self.engine_events)
+    }
+
+    pub fn get_stats(&self) -> Stats {
+        self.stats.get_stats(self.get_duration())
+    }
+
+    /// Name of the mode being played, which has its own leaderboard: the name of the piece set,
+    /// or the name of the puzzle.
+    pub fn get_mode(&self) -> String {
+        match &self.puzzle {
+            Some(puzzle_state) => format!("puzzle: {}", puzzle_state.puzzle.name),
+            None => self.piece_set.name.clone()
+        }
+    }
+
+    /// Stop or resume emitting events, e.g. to quickly simulate part of a game.
+    pub fn set_muted(&mut self, muted: bool) {
+        self.emitter.set_muted(muted);
+    }
+
+    /// Apply a command of the player. Accepted commands are recorded together with the current
+    /// tick, to be saved in the replay.
+    pub fn process_command(&mut self, command: Command) -> CommandResult {
+        // Early return if game is over
+        if self.game_over {
+            return CommandResult::GameOver;
+        }
+
+        let accepted = match command {
+            Command::Down => { self.process_move((1, 0)) },
+            Command::Left => { self.process_move((0, -1)) },
+            Command::Right => { self.process_move((0, 1)) },
+            Command::Clockwise => { self.process_rotation(Rotation::Clockwise) },
+            Command::CounterClockwise => { self.process_rotation(Rotation::CounterClockwise) },
+            Command::HardDrop => { self.process_hard_drop(); true }
+        };
+        if !accepted {
+            return CommandResult::Blocked;
+        }
+
+        if !matches!(command, Command::Down | Command::HardDrop) {
+            self.piece_inputs += 1;
+        }
+        self.recorded_inputs.push(ReplayInput {
+            tick: self.tick_count,
+            command
+        });
+        CommandResult::Accepted
+    }
+
+    fn reset_wait_ticks(&mut self) {
+        self.wait_ticks_remaining = match self.level {
+            0..=8 => 48 - 5*self.level,
+            9 => 6,
+            10..=12 => 5,
+            13..=15 => 4,
+            16..=18 => 3,
+            19..=28 => 2,
+            _ => 1
+        }
+    }
+
+    pub fn process_move(&mut self, step: (i32, i32)) -> bool {
+        match self.check_move(&self.current_tetromino, &step) {
+            Ok(_) => {
+                self.current_tetromino.move_pos(step);
+                self.last_move_was_rotation = false;
+                self.emit_current_tetromino();
+                true
+            },
+            Err(_) => false
+        }
+    }
+
+    pub fn process_hard_drop(&mut self) {
+        let step = (1, 0);
+        let mut n = 0;
+        while self.check_move(&self.current_tetromino, &step).is_ok() {
+            self.current_tetromino.move_pos(step);
+            n += 1;
+        }
+        if n > 0 {
+            self.last_move_was_rotation = false;
+        }
+        self.lock_current_tetromino();
+    }
+
+    pub fn process_rotation(&mut self, direction: Rotation) -> bool {
+        match rotate_on_board(&self.board, &self.current_tetromino, direction) {
+            Some(tetromino) => {
+                self.current_tetromino = tetromino;
+                self.last_move_was_rotation = true;
+                self.emit_current_tetromino();
+                true
+            }
+            None => false
+        }
+    }
+
+    fn check_row_full(&self, row_index: usize) -> bool {
+        let mut row_full = true;
+        for j in 0..BOARD_COLS {
+            if self.board[row_index][j] == '_' {
+                row_full = false;
+                break;
+            }
+        }
+        row_full
+    }
+
+    fn clear_full_rows(&mut self) -> i32 {
+        let mut n_cleared: i32 = 0;
+
+        for i in (0..BOARD_ROWS).rev() {
+            // While loop because if the row is full the other rows are moved down.
+            // Another full row can take the place of the cleared row, which also has to be cleared.
+            while self.check_row_full(i) {
+                // Move all the rows above i one position down
+                for other_row_index in (0..i).rev() {
+                    for j in 0..BOARD_COLS {
+                        self.board[other_row_index+1][j] = self.board[other_row_index][j];
+                    }
+                }
+                n_cleared += 1;
+            }
+        }
+
+        // Make the top rows clear
+        for i in 0..n_cleared as usize {
+            for j in 0..BOARD_COLS {
+                self.board[i][j] = '_';
+            }
+        }
+
+        // Update board and score if rows have been cleared
+        if n_cleared > 0 {
+            self.emitter.emit(GameEvent::Board { board: Box::new(self.board) });
+            self.update_score(n_cleared);
+            self.update_level(n_cleared);
+        }
+        n_cleared
+    }
+
+    fn check_move(
+        &self,
+        tetromino: &Tetromino,
+        step: &(i32, i32)) -> Result<(), MoveNotAllowedError> {
+        check_move(&self.board, tetromino, step)
+    }
+
+    fn is_occupied(&self, pos: (i32, i32)) -> bool {
+        // Positions outside the board count as occupied
+        if pos.0 < 0 || pos.0 >= BOARD_ROWS as i32 || pos.1 < 0 || pos.1 >= BOARD_COLS as i32 {
+            return true;
+        }
+        self.board[pos.0 as usize][pos.1 as usize] != '_'
+    }
+
+    fn is_t_spin(&self) -> bool {
+        // A T-spin is a T-tetromino which is rotated into its final position, with at least
+        // three of the four corners diagonally adjacent to its center occupied
+        let positions = self.current_tetromino.get_occupied_positions();
+        if self.current_tetromino.get_shape_name() != 'T'
+            || positions.len() != 4
+            || !self.last_move_was_rotation {
+            return false;
+        }
+
+        // The center is the only block of the T which touches all three other blocks
+        let center = positions.iter().find(|pos| {
+            positions.iter()
+                .filter(|other| (other.0 - pos.0).abs() + (other.1 - pos.1).abs() == 1)
+                .count() == 3
+        });
+
+        match center {
+            None => false,
+            Some(center) => {
+                let corners = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+                let n_occupied = corners.iter()
+                    .filter(|corner| self.is_occupied((center.0 + corner.0, center.1 + corner.1)))
+                    .count();
+                n_occupied >= 3
+            }
+        }
+    }
+
+    fn add_current_tetromino_to_board(&mut self) -> LockResult {
+        // Makes the current tetromino part of the frozen blocks on the board
+        // Clears full rows if the current block completes them
+        let shape_name = self.current_tetromino.get_shape_name();
+        let t_spin = self.is_t_spin();
+        for occupied_pos in self.current_tetromino.get_occupied_positions() {
+            let i = occupied_pos.0 as usize;
+            let j = occupied_pos.1 as usize;
+            self.board[i][j] = shape_name;
+        }
+        let lines_cleared = self.clear_full_rows();
+        self.emitter.emit(GameEvent::Board { board: Box::new(self.board) });
+
+        let board_empty = self.board.iter().all(|row| row.iter().all(|cell| *cell == '_'));
+        LockResult {
+            shape_name,
+            lines_cleared,
+            t_spin,
+            perfect_clear: lines_cleared > 0 && board_empty,
+        }
+    }
+
+    /// Locks the current tetromino on the board and continues with the next tetromino. Returns
+    /// false if the game ended, either because the player is game-over or the puzzle is finished.
+    fn lock_current_tetromino(&mut self) -> bool {
+        // Placements which can not be reached by dropping the piece are not checked for finesse
+        let optimal = finesse::optimal_inputs(&self.board, &self.current_tetromino)
+            .filter(|optimal| self.piece_inputs as usize > optimal.len());
+        let finesse_fault = optimal.is_some();
+        if let Some(optimal) = optimal {
+            self.emitter.emit(GameEvent::FinesseFault {
+                piece: self.current_tetromino.get_shape_name(),
+                inputs: self.piece_inputs,
+                optimal,
+            });
+            if self.finesse_training {
+                self.stats.record_finesse_fault();
+                self.restart_piece();
+                return true;
+            }
+        }
+        let lock_result = self.add_current_tetromino_to_board();
+        self.last_move_was_rotation = false;
+        self.pieces_placed += 1;
+        self.stats.record_lock(&lock_result, finesse_fault);
+        self.engine_events.push(EngineEvent::PieceLocked {
+            clear_type: ClearType::from_lock_result(&lock_result),
+            combo: self.stats.get_combo(),
+            perfect_clear: lock_result.perfect_clear,
+            total_lines: self.total_lines_cleared,
+            time_played: self.get_duration(),
+        });
+
+        // Clearing lines cancels the pending garbage before it is sent to the opponent, the
+        // garbage rises when a piece is locked without clearing lines
+        let pending_lines = self.garbage.pending_lines();
+        self.outgoing_garbage += self.garbage.attack(
+            ClearType::from_lock_result(&lock_result),
+            self.stats.get_combo(),
+            lock_result.perfect_clear
+        );
+        if self.garbage.pending_lines() != pending_lines {
+            self.emit_pending_garbage();
+        }
+        if lock_result.lines_cleared == 0 && !self.raise_garbage() {
+            self.top_out();
+            self.emit_stats();
+            return false;
+        }
+
+        if let Some(puzzle_state) = &mut self.puzzle {
+            match puzzle_state.update(&lock_result) {
+                PuzzleStatus::InProgress => {}
+                status => {
+                    self.finish_puzzle(status);
+                    self.emit_stats();
+                    return false;
+                }
+            }
+        }
+
+        let success = self.set_new_tetromino().is_ok();
+        if !success {
+            // Send the final statistics of the finished game
+            self.emit_stats();
+        }
+        success
+    }
+
+    /// Push the board up by the garbage which is ready to rise. Returns false if blocks were
+    /// pushed off the top of the board.
+    fn raise_garbage(&mut self) -> bool {
+        let mut rows = self.garbage.take_ready_rows();
+        if rows.is_empty() {
+            return true;
+        }
+        // Rows which would rise beyond the top of the board are pushed off anyway
+        let lines = rows.len().min(BOARD_ROWS);
+        rows.drain(..rows.len() - lines);
+        let topped_out = self.board[..lines].iter().any(|row| row.iter().any(|cell| *cell != '_'));
+
+        self.board.copy_within(lines.., 0);
+        self.board[BOARD_ROWS - lines..].copy_from_slice(&rows);
+        self.emitter.emit(GameEvent::Board { board: Box::new(self.board) });
+        self.emit_pending_garbage();
+        !topped_out
+    }
+
+    fn top_out(&mut self) {
+        self.game_over = true;
+        self.emitter.emit(GameEvent::GameOver);
+        if self.puzzle.is_some() {
+            self.finish_puzzle(PuzzleStatus::Failure);
+        }
+    }
+
+    fn restart_piece(&mut self) {
+        // The spawn position was free when the piece spawned, and the board has not changed
+        self.current_tetromino = spawn_tetromino(self.current_tetromino.get_shape().in_spawn_state());
+        self.last_move_was_rotation = false;
+        self.piece_inputs = 0;
+        self.reset_wait_ticks();
+        self.emit_current_tetromino();
+    }
+
+    fn finish_puzzle(&mut self, status: PuzzleStatus) {
+        // The game ends when the puzzle is finished, but the player is not game-over
+        self.game_over = true;
+        if let Some(puzzle_state) = &mut self.puzzle {
+            if status == PuzzleStatus::Failure {
+                puzzle_state.fail();
+            }
+            let name = puzzle_state.puzzle.name.clone();
+            match status {
+                PuzzleStatus::Success => self.emitter.emit(GameEvent::PuzzleSuccess { name }),
+                _ => self.emitter.emit(GameEvent::PuzzleFailure { name }),
+            }
+        }
+    }
+
+    /// Update the score based on the number of cleared rows.
+    fn update_score(&mut self, n_lines_cleared: i32) {
+        let base_points = match n_lines_cleared {
+            0 => 0,
+            1 => 100,
+            2 => 300,
+            3 => 500,
+            4 => 800,
+            _ => 0 // This should not occur
+        };
+        let points = base_points * (self.level+1);
+        self.score += points;
+        self.emitter.emit(GameEvent::Score { score: self.score });
+        self.emitter.emit(GameEvent::ScoreIncrease { points });
+    }
+
+    fn total_lines_for_level(level: i32) -> i32 {
+        if level < 1 {
+            10
+        }
+        else {
+            10 * level + Self::total_lines_for_level(level-1)
+        }
+    }
+
+    /// Lines which have to be cleared to leave the given level, counted from the start level,
+    /// so a game started on a higher level does not have to catch up on the lower levels.
+    fn lines_needed_for_level(&self, level: i32) -> i32 {
+        let skipped = match self.start_level {
+            0 => 0,
+            start_level => Self::total_lines_for_level(start_level - 1)
+        };
+        Self::total_lines_for_level(level) - skipped
+    }
+
+    fn update_level(&mut self, n_lines_cleared: i32) {
+        self.total_lines_cleared += n_lines_cleared;
+        let lines_needed = self.lines_needed_for_level(self.level);
+
+        if self.total_lines_cleared >= lines_needed{
+            self.level += 1;
+            self.emitter.emit(GameEvent::Level { level: self.level });
+            self.engine_events.push(EngineEvent::LevelUp { level: self.level });
+        }
+    }
+    /// Forwards the game a single tick. Returns true if the tick succeeded. Returns false if
+    /// the tick fails because the player is game-over.
+    pub fn tick(&mut self) -> bool {
+        if self.game_over {
+            // Do not forward the game a tick if the game is over
+            return false;
+        }
+        self.tick_count += 1;
+        if self.tick_count.is_multiple_of(STATS_INTERVAL) {
+            self.emit_stats();
+        }
+        if self.garbage.tick() {
+            self.emit_pending_garbage();
+        }
+
+        if self.wait_ticks_remaining > 0 {
+            // Only decrease counter if this tick does not forward the game
+            self.wait_ticks_remaining -= 1;
+            return true;
+        }
+
+        self.reset_wait_ticks();
+
+        let step = (1, 0);
+        let result = self.check_move(&self.current_tetromino, &step);
+        match result {
+            Ok(_) => {
+                self.current_tetromino.move_pos(step);
+                self.last_move_was_rotation = false;
+                self.emit_current_tetromino();
+                true
+            },
+            Err(_) => self.lock_current_tetromino()
+        }
+    }
+
+    fn set_new_tetromino(&mut self) -> Result<(), ()>{
+        self.current_tetromino = self.next_tetromino.clone();
+        self.current_tetromino.move_pos(spawn_position(
+            self.current_tetromino.get_size(),
+            self.current_tetromino.get_spawn_offset()
+        ));
+
+        // Game over if newly placed block overlaps with board
+        match self.check_move(&self.current_tetromino, &(0, 0)) {
+            Ok(_) => {
+                self.piece_inputs = 0;
+                self.next_tetromino = Tetromino::new(
+                    (0, 0),
+                    self.tetromino_shape_generator.make_random()
+                );
+
+                self.emit_current_tetromino();
+                self.emitter.emit(GameEvent::NextTetromino((&self.next_tetromino).into()));
+                self.emit_preview();
+
+                Ok(())
+            },
+            Err(MoveNotAllowedError::OverlapsWithOccupied) => {
+                self.top_out();
+                Err(())
+            }
+            // Other err is not expected to occur.
+            Err(_) => panic!("Unexpected error encountered when creating next tetromino.")
+        }
+    }
+
+    /// Start a new game with a random seed.
+    pub fn reset(&mut self) {
+        self.restart(rand::random());
+    }
+
+    /// Start a new game in which the pieces are generated from the given seed.
+    pub fn restart(&mut self, seed: u64) {
+        self.seed = seed;
+        self.tetromino_shape_generator = TetrominoShapeGenerator::new(&self.piece_set, seed);
+        self.board = [['_'; BOARD_COLS]; BOARD_ROWS];
+        if let Some(puzzle_state) = &mut self.puzzle {
+            puzzle_state.restart();
+            self.board = puzzle_state.puzzle.make_board();
+            // The shapes are checked when the puzzle is set, so they are available
+            let shapes = puzzle_state.puzzle.make_shapes(&self.tetromino_shape_generator);
+            self.tetromino_shape_generator.queue_shapes(shapes.unwrap_or_default());
+        }
+        self.current_tetromino = spawn_tetromino(self.tetromino_shape_generator.make_random());
+        self.next_tetromino = Tetromino::new(
+            (0, 0), self.tetromino_shape_generator.make_random()
+        );
+        self.level = self.start_level;
+        self.score = 0;
+        self.total_lines_cleared = 0;
+        self.game_over = false;
+        self.last_move_was_rotation = false;
+        self.tick_count = 0;
+        self.pieces_placed = 0;
+        self.recorded_inputs.clear();
+        self.high_score_submitted = false;
+        self.stats = StatsCollector::default();
+        self.stats_recorded = false;
+        self.piece_inputs = 0;
+        self.garbage.reset(seed);
+        self.outgoing_garbage = 0;
+        self.engine_events.clear();
+        self.reset_wait_ticks();
+        self.emit_all();
+    }
+
+    /// Play new games with the given rules, e.g. to go back to the rules of the player after a
+    /// replay. The game in progress is not changed.
+    pub fn set_rules(&mut self, rules: &GameRules) {
+        self.piece_set = rules.piece_set.clone();
+        self.puzzle = rules.puzzle.clone().map(PuzzleState::new);
+        self.start_level = rules.start_level;
+        self.finesse_training = rules.finesse_training;
+        self.emit_puzzle();
+    }
+
+    /// Start a new game with the given rules and seed, e.g. to play back a replay.
+    pub fn restart_with_rules(&mut self, rules: &GameRules, seed: u64) {
+        self.set_rules(rules);
+        self.restart(seed);
+    }
+
+    pub fn save_state(&self) -> SavedGame {
+        SavedGame {
+            version: SAVE_VERSION,
+            board: self.board,
+            current_tetromino: self.current_tetromino.clone(),
+            next_tetromino: self.next_tetromino.clone(),
+            tetromino_shape_generator: self.tetromino_shape_generator.clone(),
+            piece_set: self.piece_set.clone(),
+            seed: self.seed,
+            level: self.level,
+            total_lines_cleared: self.total_lines_cleared,
+            score: self.score,
+            wait_ticks_remaining: self.wait_ticks_remaining,
+            game_over: self.game_over,
+            last_move_was_rotation: self.last_move_was_rotation,
+            tick_count: self.tick_count,
+            pieces_placed: self.pieces_placed,
+            puzzle: self.puzzle.clone(),
+            recorded_inputs: self.recorded_inputs.clone(),
+            stats: self.stats.clone(),
+            piece_inputs: self.piece_inputs,
+            start_level: self.start_level,
+            finesse_training: self.finesse_training,
+        }
+    }
+
+    /// Continue a saved game exactly where it was left.
+    pub fn restore_state(&mut self, saved_game: SavedGame) {
+        self.board = saved_game.board;
+        self.current_tetromino = saved_game.current_tetromino;
+        self.next_tetromino = saved_game.next_tetromino;
+        self.tetromino_shape_generator = saved_game.tetromino_shape_generator;
+        self.piece_set = saved_game.piece_set;
+        self.seed = saved_game.seed;
+        self.level = saved_game.level;
+        self.start_level = saved_game.start_level;
+        self.finesse_training = saved_game.finesse_training;
+        self.total_lines_cleared = saved_game.total_lines_cleared;
+        self.score = saved_game.score;
+        self.wait_ticks_remaining = saved_game.wait_ticks_remaining;
+        self.game_over = saved_game.game_over;
+        self.last_move_was_rotation = saved_game.last_move_was_rotation;
+        self.tick_count = saved_game.tick_count;
+        self.pieces_placed = saved_game.pieces_placed;
+        self.puzzle = saved_game.puzzle;
+        self.recorded_inputs = saved_game.recorded_inputs;
+        self.stats = saved_game.stats;
+        self.piece_inputs = saved_game.piece_inputs;
+        self.stats_recorded = false;
+        self.high_score_submitted = false;
+        // Games with garbage are not saved
+        self.garbage.reset(saved_game.seed);
+        self.outgoing_garbage = 0;
+        self.engine_events.clear();
+        self.emit_all();
+        self.emit_puzzle();
+    }
+
+    /// Make a high score entry of the finished game for the player with the given name. Returns
+    /// None if the game is not finished, or the score of the game was already submitted.
+    pub fn take_high_score(&mut self, name: &str) -> Option<HighScore> {
+        if !self.game_over || self.high_score_submitted {
+            return None;
+        }
+        self.high_score_submitted = true;
+
+        let date = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        Some(HighScore {
+            name: name.to_string(),
+            score: self.score,
+            lines: self.total_lines_cleared,
+            level: self.level,
+            duration: self.get_duration(),
+            date,
+            seed: self.seed,
+        })
+    }
+
+    /// The statistics of the game to add to the lifetime statistics. Returns None if the game has
+    /// not been started, or its statistics were already taken.
+    pub fn take_final_stats(&mut self) -> Option<Stats> {
+        if self.tick_count == 0 || self.stats_recorded {
+            return None;
+        }
+        self.stats_recorded = true;
+        Some(self.get_stats())
+    }
+
+    pub fn get_rules(&self) -> GameRules {
+        GameRules {
+            piece_set: self.piece_set.clone(),
+            puzzle: self.puzzle.as_ref().map(|puzzle_state| puzzle_state.puzzle.clone()),
+            tick_rate: TICK_RATE,
+            start_level: self.start_level,
+            finesse_training: self.finesse_training,
+        }
+    }
+
+    /// The recording of the game so far, which can be played back to reproduce the game.
+    pub fn get_replay(&self) -> Replay {
+        Replay {
+            version: REPLAY_VERSION,
+            seed: self.seed,
+            rules: self.get_rules(),
+            inputs: self.recorded_inputs.clone(),
+            score: self.score,
+            lines: self.total_lines_cleared,
+            ticks: self.tick_count,
+        }
+    }
+
+    /// Play the given puzzle, or the normal game if None. The game is reset to the start of the
+    /// puzzle. Fails if the puzzle uses pieces which are not in the current piece set.
+    pub fn set_puzzle(&mut self, puzzle: Option<Puzzle>) -> Result<(), String> {
+        if let Some(puzzle) = &puzzle {
+            puzzle.make_shapes(&self.tetromino_shape_generator)?;
+        }
+        self.puzzle = puzzle.map(PuzzleState::new);
+        self.reset();
+        self.emit_puzzle();
+        Ok(())
+    }
+
+    /// Play with the pieces of the given piece set. Puzzles are made for a specific piece set,
+    /// so a puzzle that is being played is stopped.
+    pub fn set_piece_set(&mut self, piece_set: PieceSet) {
+        self.piece_set = piece_set;
+        self.puzzle = None;
+        self.reset();
+    }
+
+    /// Level new games start at. The game in progress is not changed.
+    pub fn set_start_level(&mut self, start_level: i32) {
+        self.start_level = start_level;
+    }
+
+    /// Restart pieces which are placed with a finesse fault, instead of locking them.
+    pub fn set_finesse_training(&mut self, finesse_training: bool) {
+        self.finesse_training = finesse_training;
+    }
+
+    pub fn set_preview_count(&mut self, preview_count: usize) {
+        self.preview_count = preview_count;
+        self.emit_preview();
+    }
+
+    pub fn get_piece_set(&self) -> &PieceSet {
+        &self.piece_set
+    }
+
+    pub fn emit_all(&mut self) {
+        self.emit_current_tetromino();
+        self.emitter.emit(GameEvent::NextTetromino((&self.next_tetromino).into()));
+        self.emit_preview();
+        self.emitter.emit(GameEvent::Score { score: self.score });
+        self.emitter.emit(GameEvent::Level { level: self.level });
+        self.emitter.emit(GameEvent::Board { board: Box::new(self.board) });
+        self.emit_pending_garbage();
+        self.emit_stats();
+    }
+
+    fn emit_pending_garbage(&self) {
+        self.emitter.emit(GameEvent::PendingGarbage {
+            lines: self.garbage.pending_lines(),
+            ready: self.garbage.ready_lines(),
+        });
+    }
+
+    fn emit_current_tetromino(&self) {
+        // The ghost shows where the current tetromino lands when it is dropped
+        let mut ghost = self.current_tetromino.clone();
+        while self.check_move(&ghost, &(1, 0)).is_ok() {
+            ghost.move_pos((1, 0));
+        }
+        self.emitter.emit(GameEvent::CurrentTetromino((&self.current_tetromino).into()));
+        self.emitter.emit(GameEvent::GhostTetromino((&ghost).into()));
+    }
+
+    fn emit_preview(&self) {
+        // The upcoming tetrominoes after the next tetromino
+        let tetrominoes: Vec<TetrominoPayload> = self.tetromino_shape_generator
+            .preview(self.preview_count.saturating_sub(1))
+            .into_iter()
+            .map(|shape| (&Tetromino::new((0, 0), shape)).into())
+            .collect();
+        self.emitter.emit(GameEvent::Preview { tetrominoes });
+    }
+
+    pub fn emit_stats(&self) {
+        self.emitter.emit(GameEvent::Stats(self.get_stats()));
+    }
+
+    fn emit_puzzle(&self) {
+        if let Some(puzzle_state) = &self.puzzle {
+            self.emitter.emit(GameEvent::Puzzle {
+                name: puzzle_state.puzzle.name.clone(),
+                description: puzzle_state.puzzle.description.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_sink::{NullSink, RecordingSink};
+    use crate::garbage::GARBAGE;
+
+    fn game_at_level(start_level: i32) -> Game {
+        let mut game = Game::new(Box::new(NullSink));
+        game.set_start_level(start_level);
+        game.restart(1);
+        game
+    }
+
+    #[test]
+    fn levels_up_after_ten_lines_from_level_zero() {
+        let mut game = game_at_level(0);
+        game.update_level(9);
+        assert_eq!(game.get_level(), 0);
+        game.update_level(1);
+        assert_eq!(game.get_level(), 1);
+        game.update_level(10);
+        assert_eq!(game.get_level(), 2);
+    }
+
+    #[test]
+    fn counts_lines_from_the_start_level() {
+        let mut game = game_at_level(5);
+        game.update_level(49);
+        assert_eq!(game.get_level(), 5);
+        game.update_level(1);
+        assert_eq!(game.get_level(), 6);
+        game.update_level(59);
+        assert_eq!(game.get_level(), 6);
+        game.update_level(1);
+        assert_eq!(game.get_level(), 7);
+    }
+
+    #[test]
+    fn rules_can_be_restored_after_a_replay() {
+        let mut game = game_at_level(0);
+        let player_rules = game.get_rules();
+        let mut replay_rules = player_rules.clone();
+        replay_rules.piece_set = PieceSet::builtin("pentomino").unwrap();
+        replay_rules.start_level = 3;
+        replay_rules.finesse_training = true;
+
+        game.restart_with_rules(&replay_rules, 2);
+        assert_eq!(game.get_level(), 3);
+        game.set_rules(&player_rules);
+        game.reset();
+        assert_eq!(game.get_level(), 0);
+        assert_eq!(game.get_piece_set().name, player_rules.piece_set.name);
+        assert!(!game.get_rules().finesse_training);
+    }
+
+    #[test]
+    fn ready_garbage_rises_below_the_locked_piece() {
+        let mut game = game_at_level(0);
+        game.set_garbage_settings(GarbageSettings { delay_ticks: 0, messiness: 0. });
+        game.receive_garbage(2);
+        let piece_cells = game.get_current_tetromino().get_occupied_positions().len();
+        game.process_command(Command::HardDrop);
+
+        let board = game.get_board();
+        let bottom = &board[BOARD_ROWS - 2..];
+        for row in bottom {
+            assert_eq!(row.iter().filter(|cell| **cell == '_').count(), 1);
+            assert_eq!(row.iter().filter(|cell| **cell == GARBAGE).count(), BOARD_COLS - 1);
+        }
+        // The piece was locked on the floor and pushed up by the garbage
+        let above = &board[..BOARD_ROWS - 2];
+        assert_eq!(above.iter().flatten().filter(|cell| **cell != '_').count(), piece_cells);
+        assert!(board[BOARD_ROWS - 3].iter().any(|cell| *cell != '_'));
+    }
+
+    #[test]
+    fn levels_up_from_a_high_start_level() {
+        let mut game = game_at_level(29);
+        game.update_level(290);
+        assert_eq!(game.get_level(), 30);
+    }
+
+    fn recording_game() -> (Game, RecordingSink) {
+        let sink = RecordingSink::new();
+        let mut game = Game::new(Box::new(sink.clone()));
+        game.restart(1);
+        sink.take_events();
+        (game, sink)
+    }
+
+    fn occupied_cells(board: &Board) -> usize {
+        board.iter().flatten().filter(|cell| **cell != '_').count()
+    }
+
+    #[test]
+    fn locking_a_piece_emits_the_board_and_the_next_piece() {
+        let (mut game, sink) = recording_game();
+        let locked = game.get_current_tetromino().clone();
+        game.process_command(Command::HardDrop);
+
+        let events = sink.take_events();
+        let board = events.iter().find_map(|event| match event {
+            GameEvent::Board { board } => Some(board),
+            _ => None,
+        });
+        assert_eq!(board.map(|board| occupied_cells(board)),
+                   Some(locked.get_occupied_positions().len()));
+        let current = events.iter().find_map(|event| match event {
+            GameEvent::CurrentTetromino(payload) => Some(payload),
+            _ => None,
+        });
+        assert_eq!(current.map(|payload| payload.name),
+                   Some(game.get_current_tetromino().get_shape_name()));
+        assert!(events.iter().any(|event| matches!(event, GameEvent::NextTetromino(_))));
+        assert!(!events.iter().any(|event| {
+            matches!(event, GameEvent::ScoreIncrease { .. } | GameEvent::GameOver)
+        }));
+    }
+
+    #[test]
+    fn clearing_a_line_emits_the_score_and_the_cleared_board() {
+        let (mut game, sink) = recording_game();
+        let generator = TetrominoShapeGenerator::new(&PieceSet::standard(), 0);
+        game.current_tetromino = spawn_tetromino(generator.make('I').unwrap());
+        // The bottom row is full except below the flat I piece
+        let columns: Vec<usize> = game.get_current_tetromino().get_occupied_positions().iter()
+            .map(|&(_, col)| col as usize)
+            .collect();
+        assert_eq!(columns.len(), 4);
+        for col in 0..BOARD_COLS {
+            if !columns.contains(&col) {
+                game.board[BOARD_ROWS - 1][col] = 'G';
+            }
+        }
+        game.process_command(Command::HardDrop);
+
+        let events = sink.take_events();
+        assert!(events.iter()
+            .any(|event| matches!(event, GameEvent::ScoreIncrease { points: 100 })));
+        assert!(events.iter().any(|event| matches!(event, GameEvent::Score { score: 100 })));
+        let last_board = events.iter().rev().find_map(|event| match event {
+            GameEvent::Board { board } => Some(board),
+            _ => None,
+        });
+        assert_eq!(last_board.map(|board| occupied_cells(board)), Some(0));
+        assert!(!events.iter().any(|event| matches!(event, GameEvent::GameOver)));
+    }
+
+    #[test]
+    fn topping_out_emits_game_over_once() {
+        let (mut game, sink) = recording_game();
+        // Rows which can not be cleared, up to just below the spawned piece
+        let lowest_row = game.get_current_tetromino().get_occupied_positions().iter()
+            .map(|&(row, _)| row as usize)
+            .max()
+            .unwrap();
+        for row in game.board[lowest_row + 1..].iter_mut() {
+            row[..BOARD_COLS - 1].fill('G');
+        }
+        game.process_command(Command::HardDrop);
+
+        let events = sink.take_events();
+        assert_eq!(events.iter().filter(|event| matches!(event, GameEvent::GameOver)).count(), 1);
+        assert!(game.get_game_over());
+        assert!(!game.tick());
+        assert!(sink.take_events().is_empty());
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::VecDeque;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{BOARD_COLS, TICK_RATE};
+use crate::stats::ClearType;
+
+/// Cells of garbage rows, the same as the filled cells of puzzle boards.
+pub const GARBAGE: char = 'G';
+
+// Extra lines sent for the number of consecutive clears, the last value is used for longer combos
+const COMBO_LINES: [u32; 12] = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+const BACK_TO_BACK_LINES: u32 = 1;
+const PERFECT_CLEAR_LINES: u32 = 10;
+
+/// Number of garbage lines sent for a kind of clear, without the combo and back-to-back bonus.
+pub fn lines_for_clear(clear_type: ClearType) -> u32 {
+    match clear_type {
+        ClearType::Single | ClearType::TSpin => 0,
+        ClearType::Double => 1,
+        ClearType::Triple | ClearType::TSpinSingle => 2,
+        ClearType::Tetris | ClearType::TSpinDouble => 4,
+        ClearType::TSpinTriple => 6,
+    }
+}
+
+/// Tetrises and T-spins which clear lines keep the back-to-back bonus going.
+fn is_difficult(clear_type: ClearType) -> bool {
+    !matches!(clear_type, ClearType::Single | ClearType::Double | ClearType::Triple | ClearType::TSpin)
+}
+
+/// When garbage rises and where its holes are.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GarbageSettings {
+    pub delay_ticks: u64, // Ticks an attack waits before it can rise
+    pub messiness: f64, // Chance that the hole moves to another column in the next row, 0 to 1
+}
+
+impl Default for GarbageSettings {
+    fn default() -> Self {
+        GarbageSettings {
+            delay_ticks: TICK_RATE as u64, // One second
+            messiness: 0.,
+        }
+    }
+}
+
+/// Lines of garbage received in a single attack.
+#[derive(Clone, Debug)]
+struct PendingAttack {
+    lines: u32,
+    ticks_remaining: u64,
+}
+
+/// The garbage received from the opponent which did not rise yet, and the state needed to
+/// compute the attacks of the player.
+pub struct GarbageQueue {
+    pending: VecDeque<PendingAttack>, // Oldest attack first
+    back_to_back: bool, // The last clear was a Tetris or a T-spin clear
+    settings: GarbageSettings,
+    rng: ChaCha8Rng, // Chooses the holes, from the seed of the game
+}
+
+impl GarbageQueue {
+    pub fn new(seed: u64, settings: GarbageSettings) -> Self {
+        GarbageQueue {
+            pending: VecDeque::new(),
+            back_to_back: false,
+            settings,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Empty the queue for a new game. The settings are kept.
+    pub fn reset(&mut self, seed: u64) {
+        *self = GarbageQueue::new(seed, self.settings);
+    }
+
+    pub fn set_settings(&mut self, settings: GarbageSettings) {
+        self.settings = settings;
+    }
+
+    pub fn get_back_to_back(&self) -> bool {
+        self.back_to_back
+    }
+
+    /// Lines of garbage which are waiting to rise.
+    pub fn pending_lines(&self) -> u32 {
+        self.pending.iter().map(|attack| attack.lines).sum()
+    }
+
+    /// Lines of garbage which rise when the next piece is locked without clearing lines.
+    pub fn ready_lines(&self) -> u32 {
+        self.pending.iter().filter(|attack| attack.ticks_remaining == 0).map(|attack| attack.lines).sum()
+    }
+
+    /// Count down the delay of the attacks. Returns true if an attack became ready to rise.
+    pub fn tick(&mut self) -> bool {
+        let mut became_ready = false;
+        for attack in self.pending.iter_mut().filter(|attack| attack.ticks_remaining > 0) {
+            attack.ticks_remaining -= 1;
+            became_ready |= attack.ticks_remaining == 0;
+        }
+        became_ready
+    }
+
+    pub fn receive(&mut self, lines: u32) {
+        if lines > 0 {
+            self.pending.push_back(PendingAttack { lines, ticks_remaining: self.settings.delay_ticks });
+        }
+    }
+
+    /// The attack of a locked piece, from its clear, the combo and the back-to-back bonus. The
+    /// attack first cancels the pending garbage, oldest first, and the lines which are left are
+    /// returned to be sent to the opponent.
+    pub fn attack(&mut self, clear_type: Option<ClearType>, combo: i32, perfect_clear: bool) -> u32 {
+        let Some(clear_type) = clear_type else { return 0; };
+        if clear_type == ClearType::TSpin {
+            // A T-spin without lines does not attack, and does not break back-to-back
+            return 0;
+        }
+
+        let mut lines = lines_for_clear(clear_type);
+        if combo > 0 {
+            lines += COMBO_LINES[(combo as usize).min(COMBO_LINES.len() - 1)];
+        }
+        let difficult = is_difficult(clear_type);
+        if difficult && self.back_to_back {
+            lines += BACK_TO_BACK_LINES;
+        }
+        self.back_to_back = difficult;
+        if perfect_clear {
+            lines += PERFECT_CLEAR_LINES;
+        }
+
+        while lines > 0 {
+            let Some(attack) = self.pending.front_mut() else { break; };
+            let cancelled = lines.min(attack.lines);
+            attack.lines -= cancelled;
+            lines -= cancelled;
+            if attack.lines == 0 {
+                self.pending.pop_front();
+            }
+        }
+        lines
+    }
+
+    /// Remove the attacks which are ready and make their rows, top row first. The rows of an
+    /// attack share their hole, unless the messiness moves it.
+    pub fn take_ready_rows(&mut self) -> Vec<[char; BOARD_COLS]> {
+        let mut rows = Vec::new();
+        while self.pending.front().is_some_and(|attack| attack.ticks_remaining == 0) {
+            let attack = self.pending.pop_front().unwrap();
+            let mut hole = self.rng.gen_range(0..BOARD_COLS);
+            for row in 0..attack.lines {
+                if row > 0 && self.rng.gen_bool(self.settings.messiness.clamp(0., 1.)) {
+                    // Move the hole to any other column
+                    hole = (hole + self.rng.gen_range(1..BOARD_COLS)) % BOARD_COLS;
+                }
+                rows.push(garbage_row(hole));
+            }
+        }
+        rows
+    }
+}
+
+/// A row of garbage with a hole in the given column.
+pub fn garbage_row(hole: usize) -> [char; BOARD_COLS] {
+    let mut row = [GARBAGE; BOARD_COLS];
+    row[hole] = '_';
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(delay_ticks: u64, messiness: f64) -> GarbageQueue {
+        GarbageQueue::new(1, GarbageSettings { delay_ticks, messiness })
+    }
+
+    #[test]
+    fn attack_follows_the_table() {
+        let mut queue = queue(0, 0.);
+        assert_eq!(queue.attack(None, 0, false), 0);
+        assert_eq!(queue.attack(Some(ClearType::Single), 0, false), 0);
+        assert_eq!(queue.attack(Some(ClearType::Double), 0, false), 1);
+        assert_eq!(queue.attack(Some(ClearType::Triple), 0, false), 2);
+        assert_eq!(queue.attack(Some(ClearType::Tetris), 0, false), 4);
+    }
+
+    #[test]
+    fn combo_adds_lines() {
+        let mut queue = queue(0, 0.);
+        assert_eq!(queue.attack(Some(ClearType::Single), 2, false), 1);
+        assert_eq!(queue.attack(Some(ClearType::Double), 4, false), 3);
+        // Longer combos than the table use its last value
+        assert_eq!(queue.attack(Some(ClearType::Single), 50, false), 5);
+    }
+
+    #[test]
+    fn back_to_back_adds_a_line() {
+        let mut queue = queue(0, 0.);
+        assert_eq!(queue.attack(Some(ClearType::Tetris), 0, false), 4);
+        assert_eq!(queue.attack(Some(ClearType::TSpinDouble), 0, false), 5);
+        assert!(queue.get_back_to_back());
+        // A T-spin without lines keeps back-to-back, a normal clear breaks it
+        assert_eq!(queue.attack(Some(ClearType::TSpin), 0, false), 0);
+        assert!(queue.get_back_to_back());
+        assert_eq!(queue.attack(Some(ClearType::Double), 0, false), 1);
+        assert!(!queue.get_back_to_back());
+        assert_eq!(queue.attack(Some(ClearType::Tetris), 0, false), 4);
+    }
+
+    #[test]
+    fn perfect_clear_adds_lines() {
+        let mut queue = queue(0, 0.);
+        assert_eq!(queue.attack(Some(ClearType::Single), 0, true), PERFECT_CLEAR_LINES);
+    }
+
+    #[test]
+    fn attack_cancels_pending_garbage_oldest_first() {
+        let mut queue = queue(10, 0.);
+        queue.receive(3);
+        queue.receive(2);
+        // The Tetris cancels the first attack and one line of the second
+        assert_eq!(queue.attack(Some(ClearType::Tetris), 0, false), 0);
+        assert_eq!(queue.pending_lines(), 1);
+        // The lines which are left after cancelling are sent
+        assert_eq!(queue.attack(Some(ClearType::Triple), 0, false), 1);
+        assert_eq!(queue.pending_lines(), 0);
+    }
+
+    #[test]
+    fn garbage_is_ready_after_the_delay() {
+        let mut queue = queue(2, 0.);
+        queue.receive(2);
+        assert_eq!((queue.pending_lines(), queue.ready_lines()), (2, 0));
+        assert!(queue.take_ready_rows().is_empty());
+        assert!(!queue.tick());
+        queue.receive(1);
+        assert!(queue.tick());
+        assert_eq!((queue.pending_lines(), queue.ready_lines()), (3, 2));
+
+        // Only the ready attack rises
+        assert_eq!(queue.take_ready_rows().len(), 2);
+        assert_eq!((queue.pending_lines(), queue.ready_lines()), (1, 0));
+        queue.receive(0);
+        assert_eq!(queue.pending_lines(), 1);
+    }
+
+    #[test]
+    fn rows_of_an_attack_share_their_hole() {
+        let mut queue = queue(0, 0.);
+        queue.receive(4);
+        let rows = queue.take_ready_rows();
+        assert_eq!(rows.len(), 4);
+        for row in &rows {
+            assert_eq!(row.iter().filter(|cell| **cell == '_').count(), 1);
+            assert_eq!(row.iter().filter(|cell| **cell == GARBAGE).count(), BOARD_COLS - 1);
+            assert_eq!(row, &rows[0]);
+        }
+    }
+
+    #[test]
+    fn messy_garbage_moves_the_hole() {
+        let mut queue = queue(0, 1.);
+        queue.receive(5);
+        let rows = queue.take_ready_rows();
+        for pair in rows.windows(2) {
+            assert_ne!(pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn garbage_row_has_a_hole() {
+        let row = garbage_row(3);
+        assert_eq!(row[3], '_');
+        assert!(row.iter().enumerate().all(|(j, cell)| j == 3 || *cell == GARBAGE));
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+pub const MAX_HIGH_SCORES: usize = 10; // Number of scores kept per mode
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: i32,
+    pub lines: i32,
+    pub level: i32,
+    pub duration: f64, // Seconds
+    pub date: u64, // Seconds since the unix epoch
+    pub seed: u64,
+}
+
+/// The best scores of each game mode, sorted from high to low.
+#[derive(Default, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    leaderboards: HashMap<String, Vec<HighScore>>,
+}
+
+impl HighScoreTable {
+    /// Load the table from the given path. An empty table is returned if there is no file yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(HighScoreTable::default());
+        }
+        storage::read_json(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        storage::write_json(path, self)
+    }
+
+    pub fn get(&self, mode: &str) -> Vec<HighScore> {
+        self.leaderboards.get(mode).cloned().unwrap_or_default()
+    }
+
+    /// Add a score to the leaderboard of the mode. Returns the position of the score on the
+    /// leaderboard, starting at 0, or None if the score is not high enough.
+    pub fn submit(&mut self, mode: &str, high_score: HighScore) -> Option<usize> {
+        let leaderboard = self.leaderboards.entry(mode.to_string()).or_default();
+
+        // Equal scores are placed below the scores which were set earlier
+        let position = leaderboard.iter()
+            .position(|other| high_score.score > other.score)
+            .unwrap_or(leaderboard.len());
+        if position >= MAX_HIGH_SCORES {
+            return None;
+        }
+
+        leaderboard.insert(position, high_score);
+        leaderboard.truncate(MAX_HIGH_SCORES);
+        Some(position)
+    }
+}
@@ -0,0 +1,37 @@
+//! The Tetris engine, without an interface. A game is driven by applying commands and
+//! forwarding it tick by tick; what happens is sent as events to an [`EventSink`].
+//!
+//! ```
+//! use tetris_core::{Command, CommandResult, Game, RecordingSink};
+//!
+//! let sink = RecordingSink::new();
+//! let mut game = Game::new(Box::new(sink.clone()));
+//! assert_eq!(game.process_command(Command::HardDrop), CommandResult::Accepted);
+//! while game.tick() {}
+//! assert!(game.get_game_over());
+//! assert!(!sink.take_events().is_empty());
+//! ```
+
+pub mod achievements;
+pub mod bot;
+pub mod command;
+pub mod event_sink;
+pub mod events;
+pub mod finesse;
+pub mod game;
+pub mod garbage;
+pub mod high_scores;
+pub mod lifetime;
+pub mod piece_set;
+pub mod placements;
+pub mod puzzle;
+pub mod replay;
+pub mod stats;
+pub mod storage;
+pub mod tbp;
+pub mod tetromino;
+
+pub use command::{Command, CommandResult};
+pub use event_sink::{EventSink, NullSink, RecordingSink};
+pub use events::GameEvent;
+pub use game::{Board, Game, BOARD_COLS, BOARD_ROWS, TICK_RATE};
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stats::{ClearType, Stats};
+use crate::storage;
+
+const MIN_PIECES_FOR_PPS: u64 = 10; // The rate of shorter games is not reliable enough
+
+/// Totals over all games the player has played, kept across sessions.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    pub games_played: u64,
+    pub total_lines: u64,
+    pub total_tetrises: u64,
+    pub hours_played: f64,
+    pub best_pps: HashMap<String, f64>, // Best pieces per second by mode
+}
+
+impl LifetimeStats {
+    /// Load the statistics from the given path. Empty statistics are returned if there is no
+    /// file yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(LifetimeStats::default());
+        }
+        storage::read_json(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        storage::write_json(path, self)
+    }
+
+    /// Add the statistics of a finished game of the given mode to the totals.
+    pub fn record_game(&mut self, mode: &str, stats: &Stats) {
+        self.games_played += 1;
+        self.total_lines += stats.lines_cleared.max(0) as u64;
+        self.total_tetrises += *stats.clears.get(&ClearType::Tetris).unwrap_or(&0) as u64;
+        self.hours_played += stats.time_played / 3600.;
+
+        if stats.pieces_placed >= MIN_PIECES_FOR_PPS {
+            let best_pps = self.best_pps.entry(mode.to_string()).or_insert(0.);
+            *best_pps = best_pps.max(stats.pieces_per_second);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{spawn_position, BOARD_COLS, BOARD_ROWS};
+
+const NO_KICKS: &str = "none";
+
+/// Definition of a single piece. The cells of each rotation state are given as (row, column)
+/// positions within a size by size grid, starting with the spawn state and continuing
+/// clockwise. Pieces are spawned with their grid centered horizontally on the board.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PieceDefinition {
+    pub name: char,
+    pub color: String,
+    #[serde(default = "default_size")]
+    pub size: usize,
+    pub rotations: Vec<Vec<(i32, i32)>>,
+    #[serde(default)]
+    pub spawn_offset: (i32, i32),
+    #[serde(default = "default_kick_table")]
+    pub kick_table: String,
+}
+
+fn default_size() -> usize {
+    4
+}
+
+fn default_kick_table() -> String {
+    NO_KICKS.to_string()
+}
+
+/// A set of pieces the game is played with. Kick tables contain the (row, column) offsets which
+/// are tried in order when a rotated piece does not fit. The "none" table is always available
+/// and only pushes pieces back from the walls.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PieceSet {
+    pub name: String,
+    pub pieces: Vec<PieceDefinition>,
+    #[serde(default)]
+    pub kick_tables: HashMap<String, Vec<(i32, i32)>>,
+}
+
+impl PieceSet {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Could not read piece set file {}: {}", path, err))?;
+        let piece_set: PieceSet = serde_json::from_str(&contents)
+            .map_err(|err| format!("Invalid piece set file {}: {}", path, err))?;
+        piece_set.validate()?;
+        Ok(piece_set)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.pieces.is_empty() {
+            return Err(format!("Piece set '{}' has no pieces", self.name));
+        }
+
+        let mut names = HashSet::new();
+        for piece in &self.pieces {
+            if piece.name == '_' || !names.insert(piece.name) {
+                return Err(format!("Invalid or duplicate piece name '{}'", piece.name));
+            }
+            if piece.size < 1 || piece.size > BOARD_COLS {
+                return Err(format!(
+                    "The size of piece '{}' must be between 1 and {}", piece.name, BOARD_COLS
+                ));
+            }
+            if piece.rotations.is_empty() {
+                return Err(format!("Piece '{}' has no rotation states", piece.name));
+            }
+
+            let n_cells = piece.rotations[0].len();
+            for cells in &piece.rotations {
+                if cells.is_empty() || cells.len() != n_cells {
+                    return Err(format!(
+                        "All rotation states of piece '{}' must have the same number of cells",
+                        piece.name
+                    ));
+                }
+                let unique_cells: HashSet<&(i32, i32)> = cells.iter().collect();
+                if unique_cells.len() != cells.len() {
+                    return Err(format!("Piece '{}' has duplicate cells", piece.name));
+                }
+                let size = piece.size as i32;
+                let out_of_grid = cells.iter().any(|(i, j)| {
+                    *i < 0 || *i >= size || *j < 0 || *j >= size
+                });
+                if out_of_grid {
+                    return Err(format!(
+                        "Piece '{}' does not fit in a {} by {} grid", piece.name, size, size
+                    ));
+                }
+            }
+
+            // The piece has to be completely on the board when it is spawned
+            let spawn_pos = spawn_position(piece.size, piece.spawn_offset);
+            let outside_board = piece.rotations[0].iter().any(|(i, j)| {
+                let row = spawn_pos.0 + i;
+                let col = spawn_pos.1 + j;
+                row < 0 || row >= BOARD_ROWS as i32 || col < 0 || col >= BOARD_COLS as i32
+            });
+            if outside_board {
+                return Err(format!("Piece '{}' spawns outside the board", piece.name));
+            }
+
+            if self.get_kicks(&piece.kick_table).is_none() {
+                return Err(format!(
+                    "Piece '{}' refers to unknown kick table '{}'", piece.name, piece.kick_table
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_kicks(&self, kick_table: &str) -> Option<Vec<(i32, i32)>> {
+        match kick_table {
+            NO_KICKS => Some(Vec::new()),
+            _ => self.kick_tables.get(kick_table).cloned()
+        }
+    }
+
+    /// The largest grid size of the pieces in the set, which is the size needed to show any
+    /// of the pieces.
+    pub fn get_max_size(&self) -> usize {
+        self.pieces.iter().map(|piece| piece.size).max().unwrap_or(0)
+    }
+
+    /// Get one of the piece sets which are built into the game by name.
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Self::standard()),
+            "pentomino" => Some(Self::pentomino()),
+            _ => None
+        }
+    }
+
+    /// The seven tetrominoes of the normal game.
+    pub fn standard() -> Self {
+        Self::from_grids("standard", &STANDARD_SHAPES)
+    }
+
+    /// The eighteen one-sided pentominoes. Mirrored pieces use the lowercase name.
+    pub fn pentomino() -> Self {
+        Self::from_grids("pentomino", &PENTOMINO_SHAPES)
+    }
+
+    fn from_grids<const N: usize>(set_name: &str, shapes: &[(char, &str, ShapeGrid<N>)]) -> Self {
+        let pieces = shapes.iter()
+            .map(|(name, color, shape)| PieceDefinition {
+                name: *name,
+                color: color.to_string(),
+                size: N,
+                rotations: rotation_states(shape),
+                spawn_offset: (0, 0),
+                kick_table: default_kick_table(),
+            })
+            .collect();
+
+        PieceSet {
+            name: set_name.to_string(),
+            pieces,
+            kick_tables: HashMap::new(),
+        }
+    }
+}
+
+type ShapeGrid<const N: usize> = [[i32; N]; N];
+
+fn rotate_clockwise<const N: usize>(shape: &ShapeGrid<N>) -> ShapeGrid<N> {
+    let mut rotated_shape: ShapeGrid<N> = [[0; N]; N];
+
+    for i in 0..N {
+        for j in 0..N {
+            rotated_shape[j][N-1-i] = shape[i][j];
+        }
+    }
+    rotated_shape
+}
+
+fn rotation_states<const N: usize>(shape: &ShapeGrid<N>) -> Vec<Vec<(i32, i32)>> {
+    // Compute the occupied cells of the four rotation states by rotating the grid clockwise
+    let mut rotations = Vec::new();
+    let mut shape = *shape;
+    for _ in 0..4 {
+        let mut cells = Vec::new();
+        for (i, row) in shape.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                if *value == 1 {
+                    cells.push((i as i32, j as i32));
+                }
+            }
+        }
+        rotations.push(cells);
+        shape = rotate_clockwise(&shape);
+    }
+    rotations
+}
+
+const STANDARD_SHAPES: [(char, &str, ShapeGrid<4>); 7] = [
+    ('I', "aqua", [
+        [0,0,0,0],
+        [0,0,0,0],
+        [1,1,1,1],
+        [0,0,0,0]]),
+    ('J', "blue", [
+        [0,0,0,0],
+        [0,1,1,1],
+        [0,0,0,1],
+        [0,0,0,0]]),
+    ('L', "darkorange", [
+        [0,0,0,0],
+        [0,1,1,1],
+        [0,1,0,0],
+        [0,0,0,0]]),
+    ('O', "gold", [
+        [0,0,0,0],
+        [0,1,1,0],
+        [0,1,1,0],
+        [0,0,0,0]]),
+    ('S', "green", [
+        [0,0,0,0],
+        [0,0,1,1],
+        [0,1,1,0],
+        [0,0,0,0]]),
+    ('T', "darkviolet", [
+        [0,0,0,0],
+        [0,1,1,1],
+        [0,0,1,0],
+        [0,0,0,0]]),
+    ('Z', "firebrick", [
+        [0,0,0,0],
+        [0,1,1,0],
+        [0,0,1,1],
+        [0,0,0,0]]),
+];
+
+const PENTOMINO_SHAPES: [(char, &str, ShapeGrid<5>); 18] = [
+    ('F', "crimson", [
+        [0,0,0,0,0],
+        [0,0,1,1,0],
+        [0,1,1,0,0],
+        [0,0,1,0,0],
+        [0,0,0,0,0]]),
+    ('f', "lightcoral", [
+        [0,0,0,0,0],
+        [0,1,1,0,0],
+        [0,0,1,1,0],
+        [0,0,1,0,0],
+        [0,0,0,0,0]]),
+    ('I', "aqua", [
+        [0,0,0,0,0],
+        [0,0,0,0,0],
+        [1,1,1,1,1],
+        [0,0,0,0,0],
+        [0,0,0,0,0]]),
+    ('L', "darkorange", [
+        [0,0,0,0,0],
+        [0,0,0,0,0],
+        [0,1,1,1,1],
+        [0,1,0,0,0],
+        [0,0,0,0,0]]),
+    ('l', "peru", [
+        [0,0,0,0,0],
+        [0,0,0,0,0],
+        [1,1,1,1,0],
+        [0,0,0,1,0],
+        [0,0,0,0,0]]),
+    ('N', "olive", [
+        [0,0,0,0,0],
+        [0,0,0,0,0],
+        [1,1,0,0,0],
+        [0,1,1,1,0],
+        [0,0,0,0,0]]),
+    ('n', "yellowgreen", [
+        [0,0,0,0,0],
+        [0,0,0,0,0],
+        [0,0,0,1,1],
+        [0,1,1,1,0],
+        [0,0,0,0,0]]),
+    ('P', "hotpink", [
+        [0,0,0,0,0],
+        [0,1,1,0,0],
+        [0,1,1,0,0],
+        [0,1,0,0,0],
+        [0,0,0,0,0]]),
+    ('p', "plum", [
+        [0,0,0,0,0],
+        [0,0,1,1,0],
+        [0,0,1,1,0],
+        [0,0,0,1,0],
+        [0,0,0,0,0]]),
+    ('T', "darkviolet", [
+        [0,0,0,0,0],
+        [0,1,1,1,0],
+        [0,0,1,0,0],
+        [0,0,1,0,0],
+        [0,0,0,0,0]]),
+    ('U', "teal", [
+        [0,0,0,0,0],
+        [0,0,0,0,0],
+        [0,1,0,1,0],
+        [0,1,1,1,0],
+        [0,0,0,0,0]]),
+    ('V', "royalblue", [
+        [0,0,0,0,0],
+        [0,1,0,0,0],
+        [0,1,0,0,0],
+        [0,1,1,1,0],
+        [0,0,0,0,0]]),
+    ('W', "seagreen", [
+        [0,0,0,0,0],
+        [0,1,0,0,0],
+        [0,1,1,0,0],
+        [0,0,1,1,0],
+        [0,0,0,0,0]]),
+    ('X', "gold", [
+        [0,0,0,0,0],
+        [0,0,1,0,0],
+        [0,1,1,1,0],
+        [0,0,1,0,0],
+        [0,0,0,0,0]]),
+    ('Y', "sienna", [
+        [0,0,0,0,0],
+        [0,0,0,0,0],
+        [0,0,1,0,0],
+        [0,1,1,1,1],
+        [0,0,0,0,0]]),
+    ('y', "tan", [
+        [0,0,0,0,0],
+        [0,0,0,0,0],
+        [0,0,1,0,0],
+        [1,1,1,1,0],
+        [0,0,0,0,0]]),
+    ('Z', "firebrick", [
+        [0,0,0,0,0],
+        [0,1,1,0,0],
+        [0,0,1,0,0],
+        [0,0,1,1,0],
+        [0,0,0,0,0]]),
+    ('z', "salmon", [
+        [0,0,0,0,0],
+        [0,0,1,1,0],
+        [0,0,1,0,0],
+        [0,1,1,0,0],
+        [0,0,0,0,0]]),
+];
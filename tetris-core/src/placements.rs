@@ -0,0 +1,188 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::command::Command;
+use crate::game::{check_move, rotate_on_board, Board};
+use crate::tetromino::{Rotation, Tetromino};
+
+const SEARCH_COMMANDS: [Command; 5] = [
+    Command::Left, Command::Right, Command::Down, Command::Clockwise, Command::CounterClockwise
+];
+
+/// A position where the piece can be locked, with the shortest sequence of commands which
+/// brings it there.
+#[derive(Clone)]
+pub struct Placement {
+    pub tetromino: Tetromino,
+    pub commands: Vec<Command>, // Ends with the hard drop which locks the piece
+}
+
+/// The tetromino after the command is applied on the board, or None if the command is blocked.
+/// A hard drop moves the tetromino down as far as possible, without locking it.
+pub fn apply_command(board: &Board, tetromino: &Tetromino, command: Command) -> Option<Tetromino> {
+    let step = match command {
+        Command::Left => (0, -1),
+        Command::Right => (0, 1),
+        Command::Down => (1, 0),
+        Command::Clockwise => return rotate_on_board(board, tetromino, Rotation::Clockwise),
+        Command::CounterClockwise => {
+            return rotate_on_board(board, tetromino, Rotation::CounterClockwise)
+        }
+        Command::HardDrop => return Some(hard_drop(board, tetromino)),
+    };
+    check_move(board, tetromino, &step).ok().map(|_| {
+        let mut moved = tetromino.clone();
+        moved.move_pos(step);
+        moved
+    })
+}
+
+pub fn hard_drop(board: &Board, tetromino: &Tetromino) -> Tetromino {
+    let mut dropped = tetromino.clone();
+    while check_move(board, &dropped, &(1, 0)).is_ok() {
+        dropped.move_pos((1, 0));
+    }
+    dropped
+}
+
+fn sorted_cells(tetromino: &Tetromino) -> Vec<(i32, i32)> {
+    let mut cells = tetromino.get_occupied_positions().clone();
+    cells.sort();
+    cells
+}
+
+/// Every distinct position where the tetromino can be locked, including tucks and spins, found
+/// by a breadth first search over the moves, rotations and soft drops. Placements which occupy
+/// the same cells are the same placement, each is given with a shortest sequence of commands.
+pub fn reachable_placements(board: &Board, tetromino: &Tetromino) -> Vec<Placement> {
+    // The rotation state is part of the search state, because the kicks of a rotation depend
+    // on it, but not of the placement
+    let state = |tetromino: &Tetromino| (tetromino.get_orientation(), sorted_cells(tetromino));
+
+    let mut paths: HashMap<_, Vec<Command>> = HashMap::new();
+    paths.insert(state(tetromino), Vec::new());
+    let mut queue = VecDeque::new();
+    queue.push_back(tetromino.clone());
+
+    let mut placements = Vec::new();
+    let mut placed = HashSet::new();
+    while let Some(current) = queue.pop_front() {
+        let path = paths[&state(&current)].clone();
+
+        // States are visited in order of the length of their path, so the first time a
+        // placement is found its sequence is the shortest
+        let dropped = hard_drop(board, &current);
+        if placed.insert(sorted_cells(&dropped)) {
+            let mut commands = path.clone();
+            commands.push(Command::HardDrop);
+            placements.push(Placement { tetromino: dropped, commands });
+        }
+
+        for command in SEARCH_COMMANDS {
+            let Some(next) = apply_command(board, &current, command) else { continue; };
+            if let Entry::Vacant(entry) = paths.entry(state(&next)) {
+                let mut next_path = path.clone();
+                next_path.push(command);
+                entry.insert(next_path);
+                queue.push_back(next);
+            }
+        }
+    }
+    placements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{spawn_tetromino, BOARD_COLS, BOARD_ROWS};
+    use crate::piece_set::PieceSet;
+    use crate::tetromino::TetrominoShapeGenerator;
+
+    fn spawn(name: char) -> Tetromino {
+        let generator = TetrominoShapeGenerator::new(&PieceSet::standard(), 0);
+        spawn_tetromino(generator.make(name).unwrap())
+    }
+
+    /// A board with the given rows at the bottom, 'X' for a filled cell.
+    fn make_board(rows: &[&str]) -> Board {
+        let mut board = [['_'; BOARD_COLS]; BOARD_ROWS];
+        for (i, row) in rows.iter().enumerate() {
+            for (j, cell) in row.chars().enumerate() {
+                board[BOARD_ROWS - rows.len() + i][j] = cell;
+            }
+        }
+        board
+    }
+
+    fn cells(cells: &[(i32, i32)]) -> Vec<(i32, i32)> {
+        let mut cells = cells.to_vec();
+        cells.sort();
+        cells
+    }
+
+    /// Check that the commands of every placement end with the hard drop and bring the piece
+    /// to the placement, and that no placement is found twice.
+    fn assert_valid_placements(board: &Board, tetromino: &Tetromino, placements: &[Placement]) {
+        let mut found = HashSet::new();
+        for placement in placements {
+            assert_eq!(placement.commands.last(), Some(&Command::HardDrop));
+            let mut replayed = tetromino.clone();
+            for command in &placement.commands {
+                replayed = apply_command(board, &replayed, *command).unwrap();
+            }
+            assert_eq!(sorted_cells(&replayed), sorted_cells(&placement.tetromino));
+            assert!(found.insert(sorted_cells(&placement.tetromino)));
+        }
+    }
+
+    fn find<'a>(placements: &'a [Placement], target: &[(i32, i32)]) -> Option<&'a Placement> {
+        placements.iter().find(|placement| sorted_cells(&placement.tetromino) == cells(target))
+    }
+
+    #[test]
+    fn finds_tucks_under_an_overhang() {
+        let board = make_board(&["XXXX______", "__________", "__________"]);
+        let tetromino = spawn('O');
+        let placements = reachable_placements(&board, &tetromino);
+        assert_valid_placements(&board, &tetromino, &placements);
+
+        let bottom = BOARD_ROWS as i32 - 1;
+        let under = [(bottom - 1, 0), (bottom - 1, 1), (bottom, 0), (bottom, 1)];
+        let tuck = find(&placements, &under).expect("The O can be tucked under the overhang");
+        assert!(tuck.commands.contains(&Command::Down));
+        // The O on top of the overhang is a different placement
+        let top = bottom - 4;
+        assert!(find(&placements, &[(top, 0), (top, 1), (top + 1, 0), (top + 1, 1)]).is_some());
+    }
+
+    #[test]
+    fn finds_t_spins() {
+        let board = make_board(&["XXX_______", "XX___XXXXX", "XXX_XXXXXX"]);
+        let tetromino = spawn('T');
+        let placements = reachable_placements(&board, &tetromino);
+        assert_valid_placements(&board, &tetromino, &placements);
+
+        let bottom = BOARD_ROWS as i32 - 1;
+        // The overhang blocks dropping the T into the slot, it has to be rotated into it
+        let slot = [(bottom - 2, 3), (bottom - 1, 2), (bottom - 1, 3), (bottom - 1, 4)];
+        let spin = find(&placements, &slot).expect("The T can be spun into the slot");
+        let last_move = spin.commands.iter().rev().find(|command| **command != Command::HardDrop);
+        assert!(matches!(last_move, Some(Command::Clockwise | Command::CounterClockwise)));
+    }
+
+    #[test]
+    fn placements_with_the_same_cells_are_found_once() {
+        // Different rotation states of the I, S and Z give the same cells once they are dropped
+        let board = make_board(&[]);
+        for name in ['I', 'S', 'Z', 'O'] {
+            let tetromino = spawn(name);
+            let placements = reachable_placements(&board, &tetromino);
+            assert_valid_placements(&board, &tetromino, &placements);
+            let expected = match name {
+                'O' => 9,
+                _ => 17, // Flat and upright in every column they fit in
+            };
+            assert_eq!(placements.len(), expected, "placements of {}", name);
+        }
+    }
+}
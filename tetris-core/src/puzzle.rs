@@ -0,0 +1,147 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{LockResult, BOARD_ROWS, BOARD_COLS};
+use crate::tetromino::{TetrominoShape, TetrominoShapeGenerator};
+
+/// The goal the player has to reach to solve a puzzle.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Objective {
+    ClearLines { lines: i32 },
+    TSpinDouble,
+    PerfectClear,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PuzzleStatus {
+    InProgress,
+    Success,
+    Failure,
+}
+
+/// A puzzle as defined in a puzzle file: a starting board, a fixed sequence of pieces and an
+/// objective which has to be reached within a number of pieces.
+///
+/// The board is given as rows of BOARD_COLS characters, '_' for an empty cell. The rows are
+/// aligned to the bottom of the board, so only the filled part of the board has to be given.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Puzzle {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub board: Vec<String>,
+    pub pieces: Vec<char>,
+    pub objective: Objective,
+    pub max_pieces: Option<usize>,
+}
+
+impl Puzzle {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("Could not read puzzle file {}: {}", path, err))?;
+        let puzzle: Puzzle = serde_json::from_str(&contents)
+            .map_err(|err| format!("Invalid puzzle file {}: {}", path, err))?;
+        puzzle.validate()?;
+        Ok(puzzle)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.board.len() > BOARD_ROWS {
+            return Err(format!("Puzzle board has more than {} rows", BOARD_ROWS));
+        }
+        for row in &self.board {
+            if row.chars().count() != BOARD_COLS {
+                return Err(format!("Puzzle board row '{}' does not have {} cells", row, BOARD_COLS));
+            }
+        }
+        if self.pieces.is_empty() {
+            return Err("Puzzle has no pieces".to_string());
+        }
+        if self.max_pieces == Some(0) {
+            return Err("Puzzle max_pieces must be at least 1".to_string());
+        }
+        if let Objective::ClearLines { lines } = self.objective {
+            if lines < 1 {
+                return Err("Puzzle objective must clear at least 1 line".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn make_board(&self) -> [[char; BOARD_COLS]; BOARD_ROWS] {
+        let mut board = [['_'; BOARD_COLS]; BOARD_ROWS];
+        let first_row = BOARD_ROWS - self.board.len();
+        for (i, row) in self.board.iter().enumerate() {
+            for (j, cell) in row.chars().enumerate() {
+                board[first_row + i][j] = cell;
+            }
+        }
+        board
+    }
+
+    /// Make the fixed sequence of shapes of the puzzle, using the pieces known by the generator.
+    pub fn make_shapes(
+        &self,
+        generator: &TetrominoShapeGenerator) -> Result<Vec<TetrominoShape>, String> {
+
+        self.pieces.iter()
+            .map(|name| generator.make(*name)
+                .ok_or_else(|| format!("Unknown piece '{}' in puzzle", name)))
+            .collect()
+    }
+
+    fn get_max_pieces(&self) -> usize {
+        self.max_pieces.unwrap_or(self.pieces.len())
+    }
+}
+
+/// Progress of the player towards the objective of the puzzle that is being played.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PuzzleState {
+    pub puzzle: Puzzle,
+    pieces_used: usize,
+    lines_cleared: i32,
+    status: PuzzleStatus,
+}
+
+impl PuzzleState {
+    pub fn new(puzzle: Puzzle) -> Self {
+        PuzzleState { puzzle, pieces_used: 0, lines_cleared: 0, status: PuzzleStatus::InProgress }
+    }
+
+    pub fn restart(&mut self) {
+        self.pieces_used = 0;
+        self.lines_cleared = 0;
+        self.status = PuzzleStatus::InProgress;
+    }
+
+    /// Update the progress with a tetromino that has been locked on the board and return the
+    /// new status of the puzzle.
+    pub fn update(&mut self, lock_result: &LockResult) -> PuzzleStatus {
+        if self.status != PuzzleStatus::InProgress {
+            return self.status;
+        }
+
+        self.pieces_used += 1;
+        self.lines_cleared += lock_result.lines_cleared;
+
+        let objective_reached = match self.puzzle.objective {
+            Objective::ClearLines { lines } => self.lines_cleared >= lines,
+            Objective::TSpinDouble => lock_result.t_spin && lock_result.lines_cleared == 2,
+            Objective::PerfectClear => lock_result.perfect_clear,
+        };
+
+        if objective_reached {
+            self.status = PuzzleStatus::Success;
+        } else if self.pieces_used >= self.puzzle.get_max_pieces() {
+            self.status = PuzzleStatus::Failure;
+        }
+        self.status
+    }
+
+    pub fn fail(&mut self) {
+        self.status = PuzzleStatus::Failure;
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::command::Command;
+use crate::game::Game;
+use crate::piece_set::PieceSet;
+use crate::puzzle::Puzzle;
+use crate::storage;
+
+pub const REPLAY_VERSION: u32 = 1;
+pub const MIN_REPLAY_SPEED: f64 = 0.25;
+pub const MAX_REPLAY_SPEED: f64 = 8.0;
+
+/// A command of the player, applied after the given number of ticks of the game.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayInput {
+    pub tick: u64,
+    pub command: Command,
+}
+
+/// Everything besides the seed and the inputs which determines how a game is played.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameRules {
+    pub piece_set: PieceSet,
+    pub puzzle: Option<Puzzle>,
+    pub tick_rate: f64,
+    #[serde(default)]
+    pub start_level: i32,
+    #[serde(default)]
+    pub finesse_training: bool,
+}
+
+/// A recorded game. Replaying the inputs on a game started with the same seed and rules
+/// gives exactly the same game.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub rules: GameRules,
+    pub inputs: Vec<ReplayInput>,
+    pub score: i32,
+    pub lines: i32,
+    pub ticks: u64,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let replay: Replay = storage::read_json(path)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!("Unsupported replay version {}", replay.version));
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        storage::write_json(path, self)
+    }
+}
+
+/// Plays back a replay by feeding its inputs to a game at the ticks they were recorded on.
+pub struct ReplayPlayer {
+    replay: Replay,
+    next_input: usize,
+    speed: f64, // Multiplier of the tick rate
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayer { replay, next_input: 0, speed: 1.0 }
+    }
+
+    pub fn get_speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f64) -> Result<(), String> {
+        if !(MIN_REPLAY_SPEED..=MAX_REPLAY_SPEED).contains(&speed) {
+            return Err(format!(
+                "Replay speed must be between {} and {}", MIN_REPLAY_SPEED, MAX_REPLAY_SPEED
+            ));
+        }
+        self.speed = speed;
+        Ok(())
+    }
+
+    /// Start the game of the replay from the beginning.
+    pub fn restart(&mut self, game: &mut Game) {
+        game.restart_with_rules(&self.replay.rules, self.replay.seed);
+        self.next_input = 0;
+    }
+
+    /// Forward the game a single tick, applying the inputs recorded before that tick. Returns
+    /// false if the game is over.
+    pub fn tick(&mut self, game: &mut Game) -> bool {
+        let inputs = &self.replay.inputs;
+        while self.next_input < inputs.len() && inputs[self.next_input].tick <= game.get_tick_count() {
+            game.process_command(inputs[self.next_input].command);
+            self.next_input += 1;
+        }
+        game.tick()
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::LockResult;
+
+/// The kinds of line clears which are counted separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClearType {
+    Single,
+    Double,
+    Triple,
+    Tetris,
+    TSpin, // A T-spin which does not clear lines
+    TSpinSingle,
+    TSpinDouble,
+    TSpinTriple,
+}
+
+impl ClearType {
+    /// The kind of clear of a locked piece, or None if it cleared no lines and was no T-spin.
+    pub fn from_lock_result(lock_result: &LockResult) -> Option<Self> {
+        match (lock_result.t_spin, lock_result.lines_cleared) {
+            (false, 0) => None,
+            (false, 1) => Some(ClearType::Single),
+            (false, 2) => Some(ClearType::Double),
+            (false, 3) => Some(ClearType::Triple),
+            // Pieces with more than four cells can clear more lines, which also count as Tetris
+            (false, _) => Some(ClearType::Tetris),
+            (true, 0) => Some(ClearType::TSpin),
+            (true, 1) => Some(ClearType::TSpinSingle),
+            (true, 2) => Some(ClearType::TSpinDouble),
+            (true, _) => Some(ClearType::TSpinTriple),
+        }
+    }
+}
+
+/// Statistics of a game, as sent to the interface.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Stats {
+    pub pieces_placed: u64,
+    pub lines_cleared: i32,
+    pub clears: BTreeMap<ClearType, u32>,
+    pub piece_distribution: BTreeMap<char, u32>, // Number of pieces placed by piece name
+    pub max_combo: i32,
+    pub finesse_faults: u32,
+    pub time_played: f64, // Seconds
+    pub pieces_per_second: f64,
+    pub lines_per_minute: f64,
+}
+
+/// Collects the statistics of a game from the pieces which are locked on the board.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StatsCollector {
+    pieces_placed: u64,
+    lines_cleared: i32,
+    clears: BTreeMap<ClearType, u32>,
+    piece_distribution: BTreeMap<char, u32>,
+    combo: i32, // Number of consecutive line clears after the first, -1 if the last piece cleared nothing
+    max_combo: i32,
+    finesse_faults: u32,
+}
+
+impl Default for StatsCollector {
+    fn default() -> Self {
+        StatsCollector {
+            pieces_placed: 0,
+            lines_cleared: 0,
+            clears: BTreeMap::new(),
+            piece_distribution: BTreeMap::new(),
+            combo: -1,
+            max_combo: 0,
+            finesse_faults: 0,
+        }
+    }
+}
+
+impl StatsCollector {
+    /// Count a piece that was locked on the board. A finesse fault means more inputs were used
+    /// to place the piece than needed.
+    pub fn record_lock(&mut self, lock_result: &LockResult, finesse_fault: bool) {
+        self.pieces_placed += 1;
+        self.lines_cleared += lock_result.lines_cleared;
+        *self.piece_distribution.entry(lock_result.shape_name).or_insert(0) += 1;
+        if let Some(clear_type) = ClearType::from_lock_result(lock_result) {
+            *self.clears.entry(clear_type).or_insert(0) += 1;
+        }
+
+        if lock_result.lines_cleared > 0 {
+            self.combo += 1;
+            self.max_combo = self.max_combo.max(self.combo);
+        } else {
+            self.combo = -1;
+        }
+
+        if finesse_fault {
+            self.record_finesse_fault();
+        }
+    }
+
+    pub fn record_finesse_fault(&mut self) {
+        self.finesse_faults += 1;
+    }
+
+    pub fn get_combo(&self) -> i32 {
+        self.combo
+    }
+
+    pub fn get_stats(&self, time_played: f64) -> Stats {
+        let (pieces_per_second, lines_per_minute) = match time_played > 0. {
+            true => (
+                self.pieces_placed as f64 / time_played,
+                self.lines_cleared as f64 * 60. / time_played
+            ),
+            false => (0., 0.)
+        };
+
+        Stats {
+            pieces_placed: self.pieces_placed,
+            lines_cleared: self.lines_cleared,
+            clears: self.clears.clone(),
+            piece_distribution: self.piece_distribution.clone(),
+            max_combo: self.max_combo,
+            finesse_faults: self.finesse_faults,
+            time_played,
+            pieces_per_second,
+            lines_per_minute,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use std::fs;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Write a value as JSON to the given path, creating the parent directories if needed.
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|err| format!("Could not create directory {}: {}", parent.display(), err))?;
+    }
+    let contents = serde_json::to_string_pretty(value)
+        .map_err(|err| format!("Could not serialize {}: {}", path.display(), err))?;
+    fs::write(path, contents)
+        .map_err(|err| format!("Could not write {}: {}", path.display(), err))
+}
+
+/// Read a value from the JSON file at the given path.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+    serde_json::from_str(&contents)
+        .map_err(|err| format!("Invalid file {}: {}", path.display(), err))
+}
@@ -0,0 +1,467 @@
+//! Support for external bots which speak the Tetris Bot Protocol (TBP), e.g. Cold Clear. The
+//! bot runs as a child process, with one JSON message per line on its stdin and stdout. See
+//! https://github.com/tetris-bot-protocol/tbp-spec for the protocol.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command as Process, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bot::{lock_on_board, Autoplayer};
+use crate::command::Command;
+use crate::game::{Board, Game, BOARD_COLS, BOARD_ROWS};
+use crate::placements::{apply_command, reachable_placements, Placement};
+use crate::tetromino::Tetromino;
+
+const BOARD_HEIGHT: usize = 40; // Rows of the board of the protocol, the bottom row first
+const QUEUE_LENGTH: usize = 6; // Pieces the bot knows about, including the current piece
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    North,
+    East,
+    South,
+    West,
+}
+
+/// Position of a piece in the coordinates of the protocol: x from the left and y from the
+/// bottom of the board, at the center of rotation of the piece.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PieceLocation {
+    #[serde(rename = "type")]
+    pub piece: char,
+    pub orientation: Orientation,
+    pub x: i32,
+    pub y: i32,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Spin {
+    None,
+    Mini,
+    Full,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Move {
+    pub location: PieceLocation,
+    pub spin: Spin,
+}
+
+/// Messages sent to the bot.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FrontendMessage {
+    Rules {},
+    Start {
+        hold: Option<char>, // The game has no hold
+        queue: Vec<char>,
+        combo: u32,
+        back_to_back: bool,
+        board: Vec<Vec<Option<char>>>,
+    },
+    Stop,
+    Suggest,
+    Play {
+        #[serde(rename = "move")]
+        placement: Move,
+    },
+    NewPiece { piece: char },
+    Quit,
+}
+
+/// Messages received from the bot. Messages of other types are ignored.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BotMessage {
+    Info { name: String, version: String, author: String },
+    Ready,
+    Error { reason: String },
+    Suggestion { moves: Vec<Move> },
+}
+
+/// Cells of the standard pieces in the north orientation, relative to the center of rotation,
+/// as (x, y) with y up.
+fn north_cells(piece: char) -> Option<[(i32, i32); 4]> {
+    match piece {
+        'I' => Some([(-1, 0), (0, 0), (1, 0), (2, 0)]),
+        'O' => Some([(0, 0), (1, 0), (0, 1), (1, 1)]),
+        'T' => Some([(-1, 0), (0, 0), (1, 0), (0, 1)]),
+        'L' => Some([(-1, 0), (0, 0), (1, 0), (1, 1)]),
+        'J' => Some([(-1, 0), (0, 0), (1, 0), (-1, 1)]),
+        'S' => Some([(-1, 0), (0, 0), (0, 1), (1, 1)]),
+        'Z' => Some([(-1, 1), (0, 1), (0, 0), (1, 0)]),
+        _ => None
+    }
+}
+
+/// The (row, column) cells on the board of the game which the piece of the move occupies,
+/// sorted, or None if the move is not on the board.
+fn move_cells(placement: &Move) -> Option<Vec<(i32, i32)>> {
+    let location = &placement.location;
+    let mut cells = north_cells(location.piece)?
+        .iter()
+        .map(|&(x, y)| match location.orientation {
+            Orientation::North => (x, y),
+            Orientation::East => (y, -x),
+            Orientation::South => (-x, -y),
+            Orientation::West => (-y, x),
+        })
+        .map(|(x, y)| (BOARD_ROWS as i32 - 1 - (location.y + y), location.x + x))
+        .collect::<Vec<_>>();
+    let on_board = cells.iter()
+        .all(|&(row, col)| (0..BOARD_ROWS as i32).contains(&row) && (0..BOARD_COLS as i32).contains(&col));
+    cells.sort();
+    on_board.then_some(cells)
+}
+
+fn sorted_cells(tetromino: &Tetromino) -> Vec<(i32, i32)> {
+    let mut cells = tetromino.get_occupied_positions().clone();
+    cells.sort();
+    cells
+}
+
+/// The board of the game in the format of the protocol: the bottom row first, with None for
+/// empty cells.
+fn protocol_board(board: &Board) -> Vec<Vec<Option<char>>> {
+    (0..BOARD_HEIGHT)
+        .map(|y| match y < BOARD_ROWS {
+            true => board[BOARD_ROWS - 1 - y].iter().map(|&cell| (cell != '_').then_some(cell)).collect(),
+            false => vec![None; BOARD_COLS],
+        })
+        .collect()
+}
+
+/// Plays the game with an external bot. The engine asks the bot for a move for each piece and
+/// plays it with the commands which bring the piece there. When the board is not what the bot
+/// expects, e.g. because garbage rose, the bot is started again from the state of the game.
+pub struct TbpBot {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    messages: mpsc::Receiver<BotMessage>,
+    started: bool, // The bot knows the state of the game
+    requested: Option<u64>, // Pieces placed when a suggestion was requested, until it arrives
+    expected_board: Option<Board>, // The board after the last move, as the bot sees it
+    target: Option<(u64, Vec<(i32, i32)>)>, // Cells where the piece with the given number is placed
+    plan: VecDeque<Command>, // Remaining commands for the current piece
+    expected: Option<Tetromino>, // The current piece as it should be before the next command
+    error: Option<String>, // The bot stops playing after an error
+}
+
+impl TbpBot {
+    /// Start the given program of the bot with the given arguments, e.g. "path/to/bot" and
+    /// ["--flag"], and wait until it is ready. Only the standard pieces are supported by the
+    /// protocol.
+    pub fn spawn(program: &str, args: &[String]) -> Result<Self, String> {
+        if program.is_empty() {
+            return Err("No bot program given".to_string());
+        }
+        let mut child = Process::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("Could not start bot '{}': {}", program, err))?;
+        let stdin = child.stdin.take().ok_or("The bot has no stdin")?;
+        let stdout = child.stdout.take().ok_or("The bot has no stdout")?;
+
+        // Messages are read on their own thread, so the game does not wait for the bot
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if let Ok(message) = serde_json::from_str(&line) {
+                    if sender.send(message).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let mut bot = TbpBot {
+            name: program.to_string(),
+            child,
+            stdin,
+            messages,
+            started: false,
+            requested: None,
+            expected_board: None,
+            target: None,
+            plan: VecDeque::new(),
+            expected: None,
+            error: None,
+        };
+        bot.handshake()?;
+        Ok(bot)
+    }
+
+    fn handshake(&mut self) -> Result<(), String> {
+        match self.messages.recv_timeout(HANDSHAKE_TIMEOUT) {
+            Ok(BotMessage::Info { name, version, author }) => {
+                self.name = format!("{} {} by {}", name, version, author);
+            }
+            _ => return Err("The bot did not send its info".to_string()),
+        }
+        self.send(&FrontendMessage::Rules {})?;
+        match self.messages.recv_timeout(HANDSHAKE_TIMEOUT) {
+            Ok(BotMessage::Ready) => Ok(()),
+            Ok(BotMessage::Error { reason }) => Err(format!("The bot does not accept the rules: {}", reason)),
+            _ => Err("The bot is not ready".to_string()),
+        }
+    }
+
+    /// Name, version and author of the bot.
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn send(&mut self, message: &FrontendMessage) -> Result<(), String> {
+        let line = serde_json::to_string(message).map_err(|err| err.to_string())?;
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|err| format!("Could not send to the bot: {}", err))
+    }
+
+    /// Tell the bot about the new piece, or start it from the state of the game if the board
+    /// is not what it expects, and ask for a move.
+    fn request_suggestion(&mut self, game: &Game) -> Result<(), String> {
+        if game.get_piece_set().name != "standard" {
+            return Err("Bots can only play with the standard pieces".to_string());
+        }
+        if self.started && self.expected_board.as_ref() == Some(game.get_board()) {
+            let piece = game.get_queue(QUEUE_LENGTH)[QUEUE_LENGTH - 1];
+            self.send(&FrontendMessage::NewPiece { piece })?;
+        } else {
+            if self.started {
+                self.send(&FrontendMessage::Stop)?;
+            }
+            self.send(&FrontendMessage::Start {
+                hold: None,
+                queue: game.get_queue(QUEUE_LENGTH),
+                combo: (game.get_combo() + 1).max(0) as u32,
+                back_to_back: game.get_back_to_back(),
+                board: protocol_board(game.get_board()),
+            })?;
+            self.started = true;
+        }
+        self.send(&FrontendMessage::Suggest)?;
+        self.requested = Some(game.get_pieces_placed());
+        Ok(())
+    }
+
+    /// Play the first suggested move which can be reached. If none can be reached the piece is
+    /// dropped, and the bot is started again for the next piece.
+    fn receive_suggestion(&mut self, game: &Game) -> Result<(), String> {
+        let moves = match self.messages.try_recv() {
+            Ok(BotMessage::Suggestion { moves }) => moves,
+            Ok(BotMessage::Error { reason }) => return Err(format!("The bot failed: {}", reason)),
+            Ok(_) | Err(mpsc::TryRecvError::Empty) => return Ok(()),
+            Err(mpsc::TryRecvError::Disconnected) => return Err("The bot stopped".to_string()),
+        };
+        let pieces_placed = game.get_pieces_placed();
+        if self.requested.take() != Some(pieces_placed) {
+            // The piece was locked by gravity while the bot was thinking
+            self.started = false;
+            return Ok(());
+        }
+
+        let placements = reachable_placements(game.get_board(), game.get_current_tetromino());
+        let chosen = moves.into_iter().find_map(|placement| {
+            let cells = move_cells(&placement)?;
+            placements.iter()
+                .find(|reachable| sorted_cells(&reachable.tetromino) == cells)
+                .map(|reachable| (placement, reachable.clone()))
+        });
+        match chosen {
+            Some((placement, reachable)) => {
+                self.send(&FrontendMessage::Play { placement })?;
+                self.follow(game, pieces_placed, reachable);
+            }
+            None => self.drop_piece(game, pieces_placed),
+        }
+        Ok(())
+    }
+
+    /// Drop the piece where it is, without a target which can be reached again.
+    fn drop_piece(&mut self, game: &Game, pieces_placed: u64) {
+        self.started = false;
+        self.target = Some((pieces_placed, Vec::new()));
+        self.plan = VecDeque::from([Command::HardDrop]);
+        self.expected = Some(game.get_current_tetromino().clone());
+    }
+
+    fn follow(&mut self, game: &Game, pieces_placed: u64, placement: Placement) {
+        self.expected_board = Some(lock_on_board(game.get_board(), &placement.tetromino).0);
+        self.target = Some((pieces_placed, sorted_cells(&placement.tetromino)));
+        self.plan = placement.commands.into();
+        self.expected = Some(game.get_current_tetromino().clone());
+    }
+
+    /// Find the commands to the target again, e.g. after gravity moved the piece.
+    fn replan(&mut self, game: &Game) {
+        let Some((pieces_placed, cells)) = self.target.clone() else { return; };
+        let reachable = reachable_placements(game.get_board(), game.get_current_tetromino())
+            .into_iter()
+            .find(|placement| sorted_cells(&placement.tetromino) == cells);
+        match reachable {
+            Some(placement) => self.follow(game, pieces_placed, placement),
+            None => self.drop_piece(game, pieces_placed),
+        }
+    }
+
+    fn step(&mut self, game: &Game) -> Result<Option<Command>, String> {
+        let pieces_placed = game.get_pieces_placed();
+        if self.requested.is_some() {
+            self.receive_suggestion(game)?;
+            if self.requested.is_some() || self.plan.is_empty() {
+                return Ok(None);
+            }
+        } else if self.target.as_ref().map(|(piece, _)| *piece) != Some(pieces_placed) {
+            // A new piece, the rest of the plan of a piece which was locked by gravity is
+            // not used
+            self.plan.clear();
+            self.request_suggestion(game)?;
+            return Ok(None);
+        }
+
+        let current = game.get_current_tetromino();
+        let on_plan = self.expected.as_ref().is_some_and(|expected| {
+            expected.get_orientation() == current.get_orientation()
+                && expected.get_occupied_positions() == current.get_occupied_positions()
+        });
+        if !on_plan {
+            self.replan(game);
+        }
+        let Some(command) = self.plan.pop_front() else { return Ok(None); };
+        self.expected = match command {
+            Command::HardDrop => None,
+            _ => apply_command(game.get_board(), current, command)
+        };
+        Ok(Some(command))
+    }
+}
+
+impl Autoplayer for TbpBot {
+    fn next_command(&mut self, game: &Game) -> Option<Command> {
+        if game.get_game_over() || self.error.is_some() {
+            return None;
+        }
+        self.step(game).unwrap_or_else(|err| {
+            self.error = Some(err);
+            None
+        })
+    }
+
+    fn discard_plan(&mut self) {
+        self.expected = None;
+    }
+
+    fn get_error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+impl Drop for TbpBot {
+    fn drop(&mut self) {
+        let _ = self.send(&FrontendMessage::Quit);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_sink::NullSink;
+    use std::process::ChildStdout;
+
+    fn location(piece: char, orientation: Orientation, x: i32, y: i32) -> Move {
+        Move { location: PieceLocation { piece, orientation, x, y }, spin: Spin::None }
+    }
+
+    /// A bot whose messages are echoed back by cat, to read what the engine sends.
+    fn echo_bot() -> (TbpBot, BufReader<ChildStdout>) {
+        let mut child = Process::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let bot = TbpBot {
+            name: "echo".to_string(),
+            child,
+            stdin,
+            messages: mpsc::channel().1,
+            started: false,
+            requested: None,
+            expected_board: None,
+            target: None,
+            plan: VecDeque::new(),
+            expected: None,
+            error: None,
+        };
+        (bot, stdout)
+    }
+
+    fn sent_types(stdout: &mut BufReader<ChildStdout>, count: usize) -> Vec<String> {
+        (0..count)
+            .map(|_| {
+                let mut line = String::new();
+                stdout.read_line(&mut line).unwrap();
+                let message: serde_json::Value = serde_json::from_str(&line).unwrap();
+                message["type"].as_str().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn maps_moves_to_cells_of_the_board() {
+        let bottom = BOARD_ROWS as i32 - 1;
+        assert_eq!(move_cells(&location('T', Orientation::North, 4, 0)),
+                   Some(vec![(bottom - 1, 4), (bottom, 3), (bottom, 4), (bottom, 5)]));
+        assert_eq!(move_cells(&location('T', Orientation::East, 4, 1)),
+                   Some(vec![(bottom - 2, 4), (bottom - 1, 4), (bottom - 1, 5), (bottom, 4)]));
+        assert_eq!(move_cells(&location('T', Orientation::South, 4, 1)),
+                   Some(vec![(bottom - 1, 3), (bottom - 1, 4), (bottom - 1, 5), (bottom, 4)]));
+        assert_eq!(move_cells(&location('T', Orientation::West, 4, 1)),
+                   Some(vec![(bottom - 2, 4), (bottom - 1, 3), (bottom - 1, 4), (bottom, 4)]));
+        assert_eq!(move_cells(&location('I', Orientation::East, 0, 2)),
+                   Some(vec![(bottom - 3, 0), (bottom - 2, 0), (bottom - 1, 0), (bottom, 0)]));
+    }
+
+    #[test]
+    fn moves_off_the_board_have_no_cells() {
+        assert_eq!(move_cells(&location('I', Orientation::North, 8, 0)), None);
+        assert_eq!(move_cells(&location('T', Orientation::North, 4, -1)), None);
+        assert_eq!(move_cells(&location('X', Orientation::North, 4, 0)), None);
+    }
+
+    #[test]
+    fn restarts_the_bot_when_the_board_does_not_match() {
+        let game = Game::new(Box::new(NullSink));
+        let (mut bot, mut stdout) = echo_bot();
+
+        bot.request_suggestion(&game).unwrap();
+        assert_eq!(sent_types(&mut stdout, 2), ["start", "suggest"]);
+
+        // The bot expects the board after its move
+        bot.expected_board = Some(*game.get_board());
+        bot.request_suggestion(&game).unwrap();
+        assert_eq!(sent_types(&mut stdout, 2), ["new_piece", "suggest"]);
+
+        // Garbage rose, so the board is not what the bot expects
+        let mut expected_board = *game.get_board();
+        expected_board[BOARD_ROWS - 1][0] = 'G';
+        bot.expected_board = Some(expected_board);
+        bot.request_suggestion(&game).unwrap();
+        assert_eq!(sent_types(&mut stdout, 3), ["stop", "start", "suggest"]);
+    }
+}
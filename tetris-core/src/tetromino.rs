@@ -0,0 +1,193 @@
+use std::collections::VecDeque;
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::piece_set::{PieceDefinition, PieceSet};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    Clockwise,
+    CounterClockwise,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TetrominoShape {
+    name: char,
+    size: usize, // The shape fills a size by size grid
+    orientation: usize, // Index of the current rotation state, 0 is the spawn state
+    rotations: Vec<Vec<(i32, i32)>>, // Occupied cells of each rotation state, clockwise
+    spawn_offset: (i32, i32),
+    kicks: Vec<(i32, i32)>,
+}
+
+impl TetrominoShape {
+    fn from_definition(piece: &PieceDefinition, kicks: Vec<(i32, i32)>) -> Self {
+        TetrominoShape {
+            name: piece.name,
+            size: piece.size,
+            orientation: 0,
+            rotations: piece.rotations.clone(),
+            spawn_offset: piece.spawn_offset,
+            kicks,
+        }
+    }
+
+    fn rotate_clockwise(&mut self) {
+        self.orientation = (self.orientation + 1) % self.rotations.len();
+    }
+
+    fn rotate_anti_clockwise(&mut self) {
+        let n_rotations = self.rotations.len();
+        self.orientation = (self.orientation + n_rotations - 1) % n_rotations;
+    }
+
+    /// The same shape in its spawn state.
+    pub fn in_spawn_state(&self) -> Self {
+        TetrominoShape { orientation: 0, ..self.clone() }
+    }
+
+    fn get_cells(&self) -> &Vec<(i32, i32)> {
+        &self.rotations[self.orientation]
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TetrominoShapeGenerator {
+    shapes: Vec<TetrominoShape>,
+    shape_permutation: Vec<TetrominoShape>,
+    current_index: usize,
+    queued_shapes: VecDeque<TetrominoShape>, // Fixed sequence handed out before the random bag
+    rng: ChaCha8Rng, // Seeded, so the same seed always gives the same sequence of shapes
+}
+
+impl TetrominoShapeGenerator {
+    pub fn new(piece_set: &PieceSet, seed: u64) -> Self {
+        // The piece set is validated when loading, so all kick tables exist
+        let shapes: Vec<TetrominoShape> = piece_set.pieces.iter()
+            .map(|piece| TetrominoShape::from_definition(
+                piece,
+                piece_set.get_kicks(&piece.kick_table).unwrap_or_default()
+            ))
+            .collect();
+
+        let mut tetromino_shape_generator = TetrominoShapeGenerator {
+            shape_permutation: shapes.clone(),
+            shapes,
+            current_index: 0,
+            queued_shapes: VecDeque::new(),
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        };
+        tetromino_shape_generator.shuffle();
+        tetromino_shape_generator
+    }
+
+    fn shuffle(&mut self) {
+        self.shape_permutation.shuffle(&mut self.rng);
+        self.current_index = 0;
+    }
+
+    /// The shape with the given name, or None if it is not in the piece set.
+    pub fn make(&self, name: char) -> Option<TetrominoShape> {
+        self.shapes.iter().find(|shape| shape.name == name).cloned()
+    }
+
+    /// Replace the queue of shapes which are handed out before any random shapes, e.g. the
+    /// fixed piece sequence of a puzzle.
+    pub fn queue_shapes(&mut self, shapes: Vec<TetrominoShape>) {
+        self.queued_shapes = shapes.into();
+    }
+
+    /// The next shapes which will be made, without changing the generator.
+    pub fn preview(&self, n: usize) -> Vec<TetrominoShape> {
+        let mut generator = self.clone();
+        (0..n).map(|_| generator.make_random()).collect()
+    }
+
+    pub fn make_random(&mut self) -> TetrominoShape {
+        if let Some(shape) = self.queued_shapes.pop_front() {
+            return shape;
+        }
+        if self.current_index >= self.shape_permutation.len() {
+            self.shuffle();
+        }
+        let result = self.shape_permutation[self.current_index].clone();
+        self.current_index += 1;
+        result
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Tetromino{
+    pos: (i32, i32), // x and y coordinate of top left corner
+    shape: TetrominoShape,
+    occupied_positions: Vec<(i32, i32)>
+}
+
+impl Tetromino {
+    pub fn new(pos: (i32, i32), shape: TetrominoShape) -> Self {
+        let mut tetromino = Tetromino {
+            pos,
+            shape,
+            occupied_positions: Vec::new()
+        };
+        tetromino.set_occupied_positions();
+        tetromino
+    }
+
+    pub fn get_shape_name(&self) -> char {
+        self.shape.name
+    }
+
+    pub fn get_shape(&self) -> &TetrominoShape {
+        &self.shape
+    }
+
+    pub fn get_occupied_positions(&self) -> &Vec<(i32, i32)> {
+        &self.occupied_positions
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.shape.size
+    }
+
+    pub fn get_spawn_offset(&self) -> (i32, i32) {
+        self.shape.spawn_offset
+    }
+
+    pub fn get_kicks(&self) -> &Vec<(i32, i32)> {
+        &self.shape.kicks
+    }
+
+    /// Index of the rotation state, 0 is the spawn state.
+    pub fn get_orientation(&self) -> usize {
+        self.shape.orientation
+    }
+
+    pub fn rotate(&mut self, direction: Rotation) {
+        match direction {
+            Rotation::Clockwise => { self.shape.rotate_clockwise(); }
+            Rotation::CounterClockwise => { self.shape.rotate_anti_clockwise(); }
+        }
+        self.set_occupied_positions();
+    }
+    pub fn move_pos(&mut self, step: (i32, i32)) {
+        self.pos = (self.pos.0 + step.0, self.pos.1 + step.1);
+        self.set_occupied_positions();
+    }
+
+    fn set_occupied_positions(&mut self) {
+        // Calculate the positions of the board which are occupied by the Tetromino, based on its
+        // position and shape
+
+        let mut occupied_postions: Vec<(i32, i32)> = Vec::new();
+
+        for (i, j) in self.shape.get_cells() {
+            let occupied_pos: (i32, i32) = (self.pos.0 + i, self.pos.1 + j);
+            occupied_postions.push(occupied_pos)
+        }
+        self.occupied_positions = occupied_postions;
+    }
+}
@@ -0,0 +1,85 @@
+//! A game has to be fully determined by its seed, its rules and the inputs of the player, so a
+//! replay reproduces the game exactly.
+
+use tetris_core::bot::{Autoplayer, Bot, Weights};
+use tetris_core::piece_set::PieceSet;
+use tetris_core::replay::ReplayPlayer;
+use tetris_core::{Command, CommandResult, Game, NullSink, RecordingSink};
+
+const MAX_TICKS: u64 = 3_000;
+const SCRIPT_TICKS: u64 = 200;
+const SCRIPT: [Command; 8] = [
+    Command::Left, Command::Clockwise, Command::Left, Command::HardDrop,
+    Command::Right, Command::Right, Command::CounterClockwise, Command::Down,
+];
+
+/// Play scripted inputs for the first pieces, then let the bot play until the game is over or
+/// MAX_TICKS passed.
+fn play(game: &mut Game) {
+    let mut bot = Bot::new(Weights::default());
+    while game.get_tick_count() < MAX_TICKS {
+        let tick = game.get_tick_count();
+        if tick < SCRIPT_TICKS {
+            if tick.is_multiple_of(5) {
+                game.process_command(SCRIPT[(tick / 5) as usize % SCRIPT.len()]);
+            }
+        } else if let Some(command) = bot.next_command(game) {
+            if game.process_command(command) != CommandResult::Accepted {
+                bot.discard_plan();
+            }
+        }
+        if !game.tick() {
+            break;
+        }
+    }
+}
+
+/// Play back the replay of the game on a new game and compare the final states.
+fn assert_replay_reproduces(game: &Game) {
+    let replay = game.get_replay();
+    let mut replayed = Game::new(Box::new(RecordingSink::new()));
+    let mut player = ReplayPlayer::new(replay.clone());
+    player.restart(&mut replayed);
+    // A game which is over ends at the input which topped it out, without another tick
+    if game.get_game_over() {
+        while player.tick(&mut replayed) {}
+    } else {
+        while replayed.get_tick_count() < replay.ticks {
+            player.tick(&mut replayed);
+        }
+    }
+
+    assert_eq!(replayed.get_tick_count(), game.get_tick_count());
+    assert_eq!(replayed.get_score(), game.get_score());
+    assert_eq!(replayed.get_lines_cleared(), game.get_lines_cleared());
+    assert_eq!(replayed.get_level(), game.get_level());
+    assert_eq!(replayed.get_pieces_placed(), game.get_pieces_placed());
+    assert_eq!(replayed.get_game_over(), game.get_game_over());
+    assert_eq!(replayed.get_board(), game.get_board());
+    assert_eq!(
+        replayed.get_current_tetromino().get_occupied_positions(),
+        game.get_current_tetromino().get_occupied_positions()
+    );
+    assert_eq!(replayed.get_queue(5), game.get_queue(5));
+}
+
+#[test]
+fn replay_reproduces_game() {
+    let mut game = Game::new(Box::new(NullSink));
+    game.restart(42);
+    play(&mut game);
+    assert!(game.get_pieces_placed() > 20);
+    assert_replay_reproduces(&game);
+}
+
+#[test]
+fn replay_reproduces_game_with_other_rules() {
+    let mut game = Game::new(Box::new(NullSink));
+    game.set_piece_set(PieceSet::builtin("pentomino").unwrap());
+    game.set_start_level(5);
+    game.set_finesse_training(true);
+    game.restart(7);
+    play(&mut game);
+    assert!(game.get_pieces_placed() > 0);
+    assert_replay_reproduces(&game);
+}
@@ -0,0 +1,46 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use ratatui::style::Color;
+use tetris_core::piece_set::PieceSet;
+
+// The CSS colors of the built-in piece sets, which the terminal does not know by name
+const CSS_COLORS: [(&str, (u8, u8, u8)); 20] = [
+    ("aqua", (0, 255, 255)),
+    ("blue", (0, 0, 255)),
+    ("crimson", (220, 20, 60)),
+    ("darkorange", (255, 140, 0)),
+    ("darkviolet", (148, 0, 211)),
+    ("firebrick", (178, 34, 34)),
+    ("gold", (255, 215, 0)),
+    ("green", (0, 128, 0)),
+    ("hotpink", (255, 105, 180)),
+    ("lightcoral", (240, 128, 128)),
+    ("olive", (128, 128, 0)),
+    ("peru", (205, 133, 63)),
+    ("plum", (221, 160, 221)),
+    ("royalblue", (65, 105, 225)),
+    ("salmon", (250, 128, 114)),
+    ("seagreen", (46, 139, 87)),
+    ("sienna", (160, 82, 45)),
+    ("tan", (210, 180, 140)),
+    ("teal", (0, 128, 128)),
+    ("yellowgreen", (154, 205, 50)),
+];
+
+/// Convert a color of a piece set, a CSS color name or hex code, to a terminal color. Unknown
+/// colors are shown as white.
+pub fn parse_color(color: &str) -> Color {
+    let color = color.to_lowercase();
+    match CSS_COLORS.iter().find(|(name, _)| *name == color) {
+        Some((_, (r, g, b))) => Color::Rgb(*r, *g, *b),
+        None => Color::from_str(&color).unwrap_or(Color::White)
+    }
+}
+
+/// The terminal color of each piece of the piece set.
+pub fn piece_colors(piece_set: &PieceSet) -> HashMap<char, Color> {
+    piece_set.pieces.iter()
+        .map(|piece| (piece.name, parse_color(&piece.color)))
+        .collect()
+}
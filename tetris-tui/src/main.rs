@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::style::Color;
+use tetris_core::piece_set::PieceSet;
+use tetris_core::{Command, Game, NullSink, TICK_RATE};
+
+mod colors;
+mod ui;
+
+/// The state of the terminal frontend.
+pub struct App {
+    game: Game,
+    colors: HashMap<char, Color>, // Color of each piece of the piece set
+    running: bool,
+    quit: bool,
+}
+
+impl App {
+    fn new(piece_set: PieceSet) -> Self {
+        // The interface reads the state of the game directly, so the events are not needed
+        let mut game = Game::new(Box::new(NullSink));
+        let colors = colors::piece_colors(&piece_set);
+        game.set_piece_set(piece_set);
+        App { game, colors, running: true, quit: false }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        // The same keys as the default keybindings of the app
+        let command = match key.code {
+            KeyCode::Left => Some(Command::Left),
+            KeyCode::Right => Some(Command::Right),
+            KeyCode::Down => Some(Command::Down),
+            KeyCode::Char('z') | KeyCode::Char('Z') => Some(Command::CounterClockwise),
+            KeyCode::Char('x') | KeyCode::Char('X') => Some(Command::Clockwise),
+            KeyCode::Char(' ') => Some(Command::HardDrop),
+            KeyCode::Char('p') => {
+                self.running = !self.running && !self.game.get_game_over();
+                None
+            }
+            KeyCode::Char('r') => {
+                self.game.reset();
+                self.running = true;
+                None
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.quit = true;
+                None
+            }
+            _ => None
+        };
+
+        // Commands are ignored while the game is paused, like in the app
+        if let Some(command) = command.filter(|_| self.running) {
+            self.game.process_command(command);
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.running && !self.game.tick() {
+            self.running = false;
+        }
+    }
+}
+
+fn run(terminal: &mut ratatui::DefaultTerminal, app: &mut App) -> io::Result<()> {
+    // The game is forwarded at the same rate as in the app
+    let tick_duration = Duration::from_secs_f64(1. / TICK_RATE);
+    let mut next_tick = Instant::now() + tick_duration;
+
+    while !app.quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        let timeout = next_tick.saturating_duration_since(Instant::now());
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key);
+                }
+            }
+        }
+        if Instant::now() >= next_tick {
+            app.tick();
+            next_tick += tick_duration;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> io::Result<()> {
+    // The name of a built-in piece set can be given, the standard pieces are used by default
+    let name = std::env::args().nth(1).unwrap_or("standard".to_string());
+    let Some(piece_set) = PieceSet::builtin(&name) else {
+        eprintln!("Unknown piece set '{}'", name);
+        std::process::exit(1);
+    };
+
+    let mut app = App::new(piece_set);
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut app);
+    ratatui::restore();
+    result
+}
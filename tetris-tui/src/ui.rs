@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::Frame;
+use tetris_core::tetromino::Tetromino;
+use tetris_core::{Game, BOARD_COLS, BOARD_ROWS};
+
+use crate::App;
+
+const BLOCK: &str = "██"; // A cell is two characters wide, so it is about square
+const EMPTY: &str = " ·";
+
+fn cell(name: char, colors: &HashMap<char, Color>) -> Span<'static> {
+    match name {
+        '_' => Span::styled(EMPTY, Style::default().fg(Color::DarkGray)),
+        _ => Span::styled(BLOCK, Style::default().fg(*colors.get(&name).unwrap_or(&Color::White)))
+    }
+}
+
+fn board_lines(game: &Game, colors: &HashMap<char, Color>) -> Vec<Line<'static>> {
+    let mut board = *game.get_board();
+    let current = game.get_current_tetromino();
+    if !game.get_game_over() {
+        for (i, j) in current.get_occupied_positions() {
+            board[*i as usize][*j as usize] = current.get_shape_name();
+        }
+    }
+    board.iter()
+        .map(|row| Line::from(row.iter().map(|name| cell(*name, colors)).collect::<Vec<_>>()))
+        .collect()
+}
+
+fn preview_lines(tetromino: &Tetromino, colors: &HashMap<char, Color>) -> Vec<Line<'static>> {
+    let size = tetromino.get_size();
+    let mut grid = vec![vec!['_'; size]; size];
+    for (i, j) in tetromino.get_occupied_positions() {
+        grid[*i as usize][*j as usize] = tetromino.get_shape_name();
+    }
+    grid.iter()
+        .map(|row| Line::from(row.iter().map(|name| cell(*name, colors)).collect::<Vec<_>>()))
+        .collect()
+}
+
+fn status(app: &App) -> &'static str {
+    if app.game.get_game_over() {
+        "GAME OVER - press r"
+    } else if !app.running {
+        "PAUSED - press p"
+    } else {
+        ""
+    }
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let board_width = BOARD_COLS as u16 * 2 + 2;
+    let board_height = BOARD_ROWS as u16 + 2;
+    let [board_area, side_area] = Layout::horizontal([
+        Constraint::Length(board_width),
+        Constraint::Length(24),
+    ]).areas(Rect { height: board_height.min(frame.area().height), ..frame.area() });
+
+    let board = Paragraph::new(board_lines(&app.game, &app.colors))
+        .block(Block::bordered().title(app.game.get_mode()));
+    frame.render_widget(board, board_area);
+
+    let preview_height = app.game.get_piece_set().get_max_size() as u16 + 2;
+    let [next_area, info_area] = Layout::vertical([
+        Constraint::Length(preview_height),
+        Constraint::Fill(1),
+    ]).areas(side_area);
+
+    let next = Paragraph::new(preview_lines(app.game.get_next_tetromino(), &app.colors))
+        .block(Block::bordered().title("Next"));
+    frame.render_widget(next, next_area);
+
+    let info = Paragraph::new(vec![
+        Line::from(format!("Score  {}", app.game.get_score())),
+        Line::from(format!("Level  {}", app.game.get_level())),
+        Line::from(format!("Lines  {}", app.game.get_lines_cleared())),
+        Line::from(""),
+        Line::from(Span::styled(status(app), Style::default().fg(Color::Yellow))),
+        Line::from(""),
+        Line::from("←/→/↓  move"),
+        Line::from("z/x    rotate"),
+        Line::from("space  hard drop"),
+        Line::from("p      pause"),
+        Line::from("r      new game"),
+        Line::from("q      quit"),
+    ]).block(Block::bordered());
+    frame.render_widget(info, info_area);
+}